@@ -3,7 +3,8 @@ use std::{thread, time::Duration};
 use std::str::FromStr;
 use std::fs::File;
 use std::fmt::Display;
-use std::process::Command;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
 use termion::event::Key;
 use termion::raw::IntoRawMode;
 use termion::input::TermRead;
@@ -13,7 +14,8 @@ use tui::style::{ Color, Style, Modifier};
 use tui::backend::TermionBackend;
 use tui::widgets::*;
 use tui::layout::{Layout, Rect, Constraint, Direction, Alignment, Margin};
-use chrono::{Datelike, Local, NaiveDate, NaiveTime, NaiveDateTime};
+use chrono::{Datelike, Timelike, TimeZone, Local, NaiveDate, NaiveTime, NaiveDateTime};
+use chrono_tz::Tz;
 use regex::Regex;
 // use num_traits::cast::FromPrimitive;
 
@@ -37,18 +39,27 @@ impl ModeSelection {
     fn next(&mut self) { match &*self.mode {
             "Calendar" => self.mode = "Contexts".to_string(),
             "Contexts" => self.mode = "Schedule".to_string(),
-            "Schedule" => self.mode = "Calendar".to_string(),
+            "Schedule" => self.mode = "Agenda".to_string(),
+            "Agenda" => self.mode = "Report".to_string(),
+            "Report" => self.mode = "Year".to_string(),
+            "Year" => self.mode = "Calendar".to_string(),
             &_ => (),
     } }
     fn prev(&mut self) { match &*self.mode {
             "Contexts" => self.mode = "Calendar".to_string(),
             "Schedule" => self.mode = "Contexts".to_string(),
-            "Calendar" => self.mode = "Schedule".to_string(),
+            "Agenda" => self.mode = "Schedule".to_string(),
+            "Report" => self.mode = "Agenda".to_string(),
+            "Year" => self.mode = "Report".to_string(),
+            "Calendar" => self.mode = "Year".to_string(),
             &_ => (),
     } }
     fn calendar(&mut self) { self.mode = "Calendar".to_string() }
     fn contexts(&mut self) { self.mode = "Contexts".to_string() }
     fn schedule(&mut self) { self.mode = "Schedule".to_string() }
+    fn agenda(&mut self) { self.mode = "Agenda".to_string() }
+    fn report(&mut self) { self.mode = "Report".to_string() }
+    fn year(&mut self) { self.mode = "Year".to_string() }
     fn reset_leader(&mut self) { self.leader = Key::Null }
 }
 
@@ -112,14 +123,21 @@ impl DateSelection {
     fn next_week(&mut self) { self.date = self.date.succ().succ().succ().succ().succ().succ().succ() }
     fn prev_day(&mut self) { self.date = self.date.pred() }
     fn next_day(&mut self) { self.date = self.date.succ() }
-    fn prev_hour(&mut self) {
-        if self.hour() > 0 { self.time = NaiveTime::from_hms(self.hour()-1, self.minute(), self.second()) }
-        else { self.time = NaiveTime::from_hms(23, self.minute(), self.second()); self.prev_day() }}
-    fn next_hour(&mut self) {
-        if self.hour() < 23 { self.time = NaiveTime::from_hms(self.hour()+1, self.minute(), self.second()) }
-        else { self.time = NaiveTime::from_hms(0, self.minute(), self.second()); self.next_day() }}
-
-    fn second(&self) -> u32 { return self.time.format("%S").to_string().parse::<u32>().unwrap() }
+
+    // start of the row of minutes the selection falls in
+    fn slot(&self, minutes: i64) -> NaiveDateTime {
+        let since_midnight = (self.hour() * 60 + self.minute()) as i64;
+        self.date.and_hms(0, 0, 0) + chrono::Duration::minutes(since_midnight / minutes * minutes)
+    }
+    fn next_slot(&mut self, minutes: i64) {
+        let next = self.slot(minutes) + chrono::Duration::minutes(minutes);
+        self.date = next.date(); self.time = next.time();
+    }
+    fn prev_slot(&mut self, minutes: i64) {
+        let prev = self.slot(minutes) - chrono::Duration::minutes(minutes);
+        self.date = prev.date(); self.time = prev.time();
+    }
+
     fn minute(&self) -> u32 { return self.time.format("%M").to_string().parse::<u32>().unwrap() }
     fn hour(&self) -> u32 { return self.time.format("%H").to_string().parse::<u32>().unwrap() }
     fn day(&self) -> u32 { return self.date.day() }
@@ -173,39 +191,1027 @@ impl FromStr for Cycle {
         }
     }
 }
-impl Cycle {  }
+impl Cycle {
+    fn name(&self) -> &str {
+        match self { Cycle::Never => "never", Cycle::Daily => "daily", Cycle::Weekly => "weekly", Cycle::Monthly => "monthly", Cycle::Yearly => "yearly" }
+    }
+    fn from_name(name: &str) -> Option<Cycle> {
+        [Cycle::Never, Cycle::Daily, Cycle::Weekly, Cycle::Monthly, Cycle::Yearly].into_iter().find(|c| c.name() == name)
+    }
+}
+
+// days of the month a monthly or yearly event falls on: 15, last, 2Tue, lastFri, 1bday, lastbday
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum DayPattern { Day(u32), LastDay, Weekday(u32, chrono::Weekday), LastWeekday(chrono::Weekday), BusinessDay(u32), LastBusinessDay }
+impl DayPattern {
+    fn parse(str: &str) -> Option<DayPattern> {
+        let lower = str.to_lowercase();
+        if lower == "last" { return Some(DayPattern::LastDay) }
+        if lower == "lastbday" { return Some(DayPattern::LastBusinessDay) }
+        if let Some(day) = lower.strip_prefix("last") { return day.parse::<chrono::Weekday>().ok().map(DayPattern::LastWeekday) }
+        let digits = lower.chars().take_while(|c| c.is_ascii_digit()).count();
+        let n = lower[..digits].parse::<u32>().ok()?;
+        let rest = &lower[digits..];
+        match rest {
+            // 15 or 15th
+            "" | "st" | "nd" | "rd" | "th" if (1..=31).contains(&n) => Some(DayPattern::Day(n)),
+            "bday" if (1..=23).contains(&n) => Some(DayPattern::BusinessDay(n)),
+            // 2Tue or 2ndTue
+            _ if (1..=5).contains(&n) => rest.parse::<chrono::Weekday>().ok()
+                .or(["st", "nd", "rd", "th"].iter().find_map(|s| rest.strip_prefix(s)?.parse::<chrono::Weekday>().ok()))
+                .map(|day| DayPattern::Weekday(n, day)),
+            _ => None,
+        }
+    }
+    fn name(&self) -> String {
+        let day = |d: &chrono::Weekday| WEEKDAYS[d.num_days_from_sunday() as usize];
+        match self {
+            DayPattern::Day(n) => n.to_string(),
+            DayPattern::LastDay => "last".to_string(),
+            DayPattern::Weekday(n, d) => format!("{}{}", n, day(d)),
+            DayPattern::LastWeekday(d) => format!("last{}", day(d)),
+            DayPattern::BusinessDay(n) => format!("{}bday", n),
+            DayPattern::LastBusinessDay => "lastbday".to_string(),
+        }
+    }
+    fn matches(&self, date: NaiveDate) -> bool {
+        let business = |d: NaiveDate| d.weekday().num_days_from_monday() < 5;
+        match self {
+            DayPattern::Day(n) => date.day() == *n,
+            DayPattern::LastDay => date.succ().month() != date.month(),
+            DayPattern::Weekday(n, d) => date.weekday() == *d && (date.day()-1)/7 + 1 == *n,
+            DayPattern::LastWeekday(d) => date.weekday() == *d && (date + chrono::Duration::days(7)).month() != date.month(),
+            DayPattern::BusinessDay(n) => business(date) && (1..=date.day()).filter(|&i| business(date.with_day(i).unwrap())).count() as u32 == *n,
+            DayPattern::LastBusinessDay => business(date)
+                && date.succ().iter_days().take_while(|d| d.month() == date.month()).all(|d| !business(d)),
+        }
+    }
+}
+
+// repeat_occurences bits from high to low: unused, Sun, Mon, ... Sat, whatever day weeks are shown starting on
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+fn weekday_bit(day: chrono::Weekday) -> u8 { 2_u8.pow(6 - day.num_days_from_sunday()) }
+
+// the week start of the region in LC_ALL, LC_TIME or LANG, e.g. en_GB.UTF-8, Sunday without one
+fn locale_week_start() -> chrono::Weekday {
+    let locale = ["LC_ALL", "LC_TIME", "LANG"].iter().filter_map(|v| std::env::var(v).ok()).find(|l| !l.is_empty()).unwrap_or("".to_string());
+    let region = locale.split(|c| c == '.' || c == '@').next().unwrap_or("").split('_').nth(1).unwrap_or("");
+    match region {
+        "" | "US" | "CA" | "MX" | "BR" | "JP" | "KR" | "TW" | "HK" | "IL" | "PH" | "IN" | "ZA" => chrono::Weekday::Sun,
+        "EG" | "AE" | "IR" | "AF" | "DZ" | "IQ" | "JO" | "KW" | "LY" | "OM" | "QA" | "SD" | "SY" => chrono::Weekday::Sat,
+        _ => chrono::Weekday::Mon,
+    }
+}
+
+// a day with a one-off, monthly or yearly event, daily and weekly ones would mark every day
+fn notable_day(sched_list: &[Event], date: NaiveDate, holidays: &[Holiday]) -> bool {
+    sched_list.iter().any(|e| e.repeat_cycle != Cycle::Daily && e.repeat_cycle != Cycle::Weekly && e.starts_on(date, holidays))
+}
+
+// the first day of the week holding date, for weeks starting on start
+fn week_begin(date: NaiveDate, start: chrono::Weekday) -> NaiveDate {
+    date - chrono::Duration::days(((date.weekday().num_days_from_monday() + 7 - start.num_days_from_monday()) % 7) as i64)
+}
 
 #[derive(Debug, PartialEq, Clone)]
-struct Event { name: String, time: NaiveTime, date: NaiveDate, duration: u8, repeat_cycle: Cycle, repeat_occurences: u8, color: Color, task_modifier: String}
+struct Event { name: String, time: NaiveTime, date: NaiveDate, duration: u8, repeat_cycle: Cycle, repeat_occurences: u8, color: Color, task_modifier: String, line: usize, tz: Option<Tz>, reminders: Vec<i64>,
+    description: String, location: String, calendar: String, file: String, skip_holidays: bool,
+    task: String, days: Vec<DayPattern> }
 impl Event {
     fn new(name: String, time: NaiveTime, date: NaiveDate, duration: u8, repeat_cycle: Cycle, repeat_occurences: u8, color: Color, task_modifier: String) -> Event {
-        Event{name, time, date, duration, repeat_cycle, repeat_occurences, color, task_modifier}
-    }
-    fn from_str(str: String) -> Event {
-        let substr: Vec<String> = str.split_whitespace().map(|s| s.to_string()).collect();
-        let color = match &*substr[0] {
-            "Red" => Color::Red, "LightRed" => Color::LightRed, "Yellow" => Color::Yellow, "LightYellow" => Color::LightYellow,
-            "Green" => Color::Green, "LightGreen" => Color::LightGreen, "Blue" => Color::Blue, "LightBlue" => Color::LightBlue,
-            "Cyan" => Color::Cyan, "LightCyan" => Color::LightCyan, "Magenta" => Color::Magenta, "LightMagenta" => Color::LightMagenta,
-            "Black" => Color::Black, "DarkGray" => Color::DarkGray, "Gray" => Color::Gray, "White" => Color::White,
-            &_ => Color::Reset,
-        };
-        let date = NaiveDate::from_ymd(substr[1].parse::<i32>().unwrap(), substr[2].parse::<u32>().unwrap(), substr[3].parse::<u32>().unwrap());
-        let time = NaiveTime::from_hms(substr[4].parse::<u32>().unwrap(), 0, 0);
-        let duration = substr[5].parse::<u8>().unwrap();
-        let repeat_cycle = Cycle::from_str(&substr[6]).unwrap();
-        let repeat_occurences = substr[7].as_bytes().iter().fold(0, |acc, &b| acc*2 + b - 48 as u8);
-        // println!("{}", format!("{:08b}", repeat_occurences));
-        let name = &substr[8];
+        Event{name, time, date, duration, repeat_cycle, repeat_occurences, color, task_modifier, line: 0, tz: None, reminders: Vec::new(),
+            description: "".to_string(), location: "".to_string(), calendar: "".to_string(), file: "".to_string(), skip_holidays: false, task: "".to_string(), days: Vec::new()}
+    }
+    // Err is the column and message of the first problem
+    fn parse(str: &str, line: usize) -> Result<Event, (usize, String)> {
+        // columns count characters, like tokenize
+        if let Some(i) = str.chars().enumerate().filter(|(_, c)| *c == '"').map(|(i, _)| i).last().filter(|_| str.matches('"').count() % 2 == 1) {
+            return Err((i+1, "unterminated quote".to_string()))
+        }
+        let tokens = tokenize(str);
+        if tokens.len() < 10 { return Err((str.chars().count()+1, format!("expected 10 columns, found {}", tokens.len()))) }
+        let columns: Vec<usize> = tokens.iter().map(|t| t.0).collect();
+        let substr: Vec<String> = tokens.into_iter().map(|t| t.1).collect();
+        let err = |i: usize, message: String| (columns[i], message);
+        let number = |i: usize, field: &str| substr[i].parse::<u32>().map_err(|_| err(i, format!("{} '{}' is not a number", field, substr[i])));
+        let color = parse_color(&substr[0]).unwrap_or(Color::Reset);
+        let year = substr[1].parse::<i32>().map_err(|_| err(1, format!("year '{}' is not a number", substr[1])))?;
+        let date = NaiveDate::from_ymd_opt(year, number(2, "month")?, number(3, "day")?)
+            .ok_or(err(1, format!("impossible date {}-{}-{}", substr[1], substr[2], substr[3])))?;
+        let time = NaiveTime::from_hms_opt(number(4, "hour")?, 0, 0).ok_or(err(4, format!("impossible hour {}", substr[4])))?;
+        let duration = substr[5].parse::<u8>().map_err(|_| err(5, format!("duration '{}' is not a number of hours", substr[5])))?;
+        let repeat_cycle = Cycle::from_str(&substr[6]).map_err(|_| err(6, format!("unknown cycle '{}'", substr[6])))?;
+        let repeat_occurences = u8::from_str_radix(&substr[7], 2).ok().filter(|_| substr[7].chars().all(|c| c == '0' || c == '1'))
+            .ok_or(err(7, format!("occurrence mask '{}' is not up to 8 binary digits", substr[7])))?;
+        if substr[8].is_empty() { return Err(err(8, "missing event name".to_string())) }
+        let name = substr[8].to_string();
         let task_modifier = substr[9].to_string();
-        Event{name: name.to_string(), time, date, duration, repeat_cycle, repeat_occurences, color, task_modifier}
+        let (mut tz, mut reminders, mut description, mut location) = (None, Vec::new(), "".to_string(), "".to_string());
+        let (mut skip_holidays, mut task, mut days) = (false, "".to_string(), Vec::new());
+        for (i, field) in substr.iter().enumerate().skip(10) {
+            match field.split_once('=') {
+                Some(("desc", text)) => description = text.to_string(),
+                Some(("loc", text)) => location = text.to_string(),
+                Some(("holidays", "skip")) => skip_holidays = true,
+                Some(("task", uuid)) => task = uuid.to_string(),
+                Some(("on", patterns)) => for pattern in patterns.split(',') {
+                    days.push(DayPattern::parse(pattern).ok_or(err(i, format!("unknown day pattern '{}'", pattern)))?)
+                }
+                Some(("tz", zone)) => tz = Some(zone.parse::<Tz>().map_err(|_| err(i, format!("unknown time zone '{}'", zone)))?),
+                Some(("remind", offsets)) => for offset in offsets.split(',') {
+                    reminders.push(offset.parse::<i64>().map_err(|_| err(i, format!("reminder '{}' is not a number of minutes", offset)))?)
+                }
+                _ => return Err(err(i, format!("unknown field '{}'", field))),
+            }
+        }
+        Ok(Event{name, time, date, duration, repeat_cycle, repeat_occurences, color, task_modifier, line, tz, reminders, description, location,
+            calendar: "".to_string(), file: "".to_string(), skip_holidays, task, days})
+    }
+    // one [[event]] table of a structured schedule file
+    fn from_table(table: &toml::Value, line: usize) -> Result<Event, String> {
+        let string = |key: &str| -> Result<Option<String>, String> {
+            match table.get(key) {
+                Some(value) => value.as_str().map(|s| Some(s.to_string())).ok_or(format!("{} must be a string", key)),
+                None => Ok(None),
+            }
+        };
+        let integer = |key: &str| -> Result<Option<i64>, String> {
+            match table.get(key) {
+                Some(value) => value.as_integer().map(Some).ok_or(format!("{} must be an integer", key)),
+                None => Ok(None),
+            }
+        };
+        let name = string("name")?.filter(|n| !n.is_empty()).ok_or("missing name")?;
+        let color = parse_color(&string("color")?.unwrap_or("".to_string())).unwrap_or(Color::Reset);
+        let start = string("start")?.ok_or("missing start")?;
+        let (date, time) = match NaiveDateTime::parse_from_str(&start, "%Y-%m-%d %H:%M") {
+            Ok(datetime) => (datetime.date(), datetime.time()),
+            Err(_) => (NaiveDate::from_ymd(1, 1, 1), NaiveTime::parse_from_str(&start, "%H:%M")
+                .map_err(|_| format!("start '{}' is not HH:MM or YYYY-MM-DD HH:MM", start))?),
+        };
+        let duration = integer("duration")?.unwrap_or(1);
+        if duration < 0 || duration > u8::MAX as i64 { return Err(format!("duration {} is not 0 to {} hours", duration, u8::MAX)) }
+        let repeat = string("repeat")?.unwrap_or("never".to_string());
+        let repeat_cycle = Cycle::from_name(&repeat).ok_or(format!("unknown repeat '{}'", repeat))?;
+        let repeat_occurences = match (string("mask")?, table.get("days")) {
+            (Some(mask), _) => {
+                u8::from_str_radix(&mask, 2).ok().filter(|_| mask.chars().all(|c| c == '0' || c == '1'))
+                    .ok_or(format!("mask '{}' is not up to 8 binary digits", mask))?
+            }
+            (None, Some(days)) => {
+                let mut mask = 0;
+                for day in days.as_array().ok_or("days must be a list")? {
+                    let day = day.as_str().unwrap_or("");
+                    let day = WEEKDAYS.iter().find(|d| **d == day).ok_or(format!("unknown day '{}'", day))?;
+                    mask |= weekday_bit(day.parse().unwrap());
+                }
+                mask
+            }
+            (None, None) => 0,
+        };
+        let task_modifier = string("context")?.unwrap_or("none".to_string());
+        let tz = match string("tz")? {
+            Some(zone) => Some(zone.parse::<Tz>().map_err(|_| format!("unknown time zone '{}'", zone))?),
+            None => None,
+        };
+        let mut reminders = Vec::new();
+        if let Some(offsets) = table.get("remind") {
+            for offset in offsets.as_array().ok_or("remind must be a list of minutes")? {
+                reminders.push(offset.as_integer().ok_or("remind must be a list of minutes")?)
+            }
+        }
+        let description = string("description")?.unwrap_or("".to_string());
+        let location = string("location")?.unwrap_or("".to_string());
+        let calendar = string("calendar")?.unwrap_or("".to_string());
+        let task = string("task")?.unwrap_or("".to_string());
+        let mut days = Vec::new();
+        if let Some(patterns) = table.get("on") {
+            for pattern in patterns.as_array().ok_or("on must be a list of day patterns")? {
+                let text = match pattern { toml::Value::Integer(n) => n.to_string(), _ => pattern.as_str().unwrap_or("").to_string() };
+                days.push(DayPattern::parse(&text).ok_or(format!("unknown day pattern '{}'", text))?)
+            }
+        }
+        let skip_holidays = match table.get("skip_holidays") {
+            Some(value) => value.as_bool().ok_or("skip_holidays must be true or false")?,
+            None => false,
+        };
+        Ok(Event{name, time, date, duration: duration as u8, repeat_cycle, repeat_occurences, color, task_modifier, line, tz, reminders, description, location,
+            calendar, file: "".to_string(), skip_holidays, task, days})
+    }
+    fn to_toml(&self, color: &str) -> String {
+        let string = |s: &str| toml::Value::String(s.to_string()).to_string();
+        let mut out = "[[event]]\n".to_string();
+        out += &format!("name = {}\n", string(&self.name));
+        // "-" takes the calendar's color
+        if color != "-" { out += &format!("color = {}\n", string(color)) }
+        if self.calendar != "" { out += &format!("calendar = {}\n", string(&self.calendar)) }
+        out += &format!("start = {}\n", string(&if self.date == NaiveDate::from_ymd(1, 1, 1) { self.time.format("%H:%M").to_string() }
+                                                   else { self.date.and_time(self.time).format("%Y-%m-%d %H:%M").to_string() }));
+        out += &format!("duration = {}\nrepeat = {}\n", self.duration, string(self.repeat_cycle.name()));
+        if self.repeat_occurences & 0b10000000 != 0 { out += &format!("mask = \"{:08b}\"\n", self.repeat_occurences) }
+        else if self.repeat_occurences != 0 {
+            let days: Vec<String> = WEEKDAYS.iter().filter(|d| self.repeat_occurences & weekday_bit(d.parse().unwrap()) != 0).map(|d| string(d)).collect();
+            out += &format!("days = [{}]\n", days.join(", "));
+        }
+        out += &format!("context = {}\n", string(&self.task_modifier));
+        if let Some(tz) = self.tz { out += &format!("tz = {}\n", string(tz.name())) }
+        if !self.reminders.is_empty() { out += &format!("remind = [{}]\n", self.reminders.iter().map(|r| r.to_string()).collect::<Vec<String>>().join(", ")) }
+        if self.description != "" { out += &format!("description = {}\n", string(&self.description)) }
+        if self.location != "" { out += &format!("location = {}\n", string(&self.location)) }
+        if self.skip_holidays { out += "skip_holidays = true\n" }
+        if self.task != "" { out += &format!("task = {}\n", string(&self.task)) }
+        if !self.days.is_empty() { out += &format!("on = [{}]\n", self.days.iter().map(|d| string(&d.name())).collect::<Vec<String>>().join(", ")) }
+        out
     }
     fn clone(&self) -> Event {
         Event{ name: self.name.clone(), time: self.time, date: self.date, duration: self.duration, repeat_cycle: self.repeat_cycle,
-            repeat_occurences: self.repeat_occurences, color: self.color, task_modifier: self.task_modifier.clone() }
+            repeat_occurences: self.repeat_occurences, color: self.color, task_modifier: self.task_modifier.clone(), line: self.line, tz: self.tz, reminders: self.reminders.clone(),
+            description: self.description.clone(), location: self.location.clone(), calendar: self.calendar.clone(), file: self.file.clone(),
+            skip_holidays: self.skip_holidays, task: self.task.clone(), days: self.days.clone() }
+    }
+    // a one-off event as a line of the whitespace schedule format
+    fn to_line(&self, color: &str) -> String {
+        let mut out = format!("{: <9} {} {} {} {: <4} {: <3} N   0         \"{}\" none",
+            color, self.date.year(), self.date.month(), self.date.day(), self.time.hour(), self.duration, self.name.replace('"', "'"));
+        if self.task != "" { out += &format!(" task={}", self.task) }
+        out
+    }
+    // a time block whose task has been completed
+    fn is_done(&self, tasks: &[Task]) -> bool { self.task != "" && tasks.iter().any(|t| t.uuid == self.task && t.status == "completed") }
+    fn starts_on(&self, date: NaiveDate, holidays: &[Holiday]) -> bool {
+        if self.skip_holidays && self.repeat_cycle != Cycle::Never && holiday(holidays, date).is_some() { return false }
+        match self.repeat_cycle {
+            Cycle::Never => date == self.date,
+            Cycle::Daily => true,
+            Cycle::Weekly => weekday_bit(date.weekday()) & self.repeat_occurences != 0,
+            Cycle::Monthly if !self.days.is_empty() => self.days.iter().any(|d| d.matches(date)),
+            Cycle::Monthly => date.day() == self.date.day(),
+            Cycle::Yearly if !self.days.is_empty() => date.month() == self.date.month() && self.days.iter().any(|d| d.matches(date)),
+            Cycle::Yearly => date.day() == self.date.day() && date.month() == self.date.month(),
+        }
+    }
+    // local (begin, end) of the occurrence starting on date, which is a date in the event's own time zone
+    fn interval(&self, date: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+        let begin = date.and_time(self.time);
+        let duration = chrono::Duration::hours(self.duration as i64);
+        match self.tz {
+            None => (begin, begin + duration),
+            Some(tz) => {
+                // a start time skipped by a DST transition, or a skipped day, begins once the clocks have gone forward
+                match (0..=48).find_map(|h| tz.from_local_datetime(&(begin + chrono::Duration::hours(h))).earliest()) {
+                    Some(begin) => (begin.with_timezone(&Local).naive_local(), (begin + duration).with_timezone(&Local).naive_local()),
+                    None => (begin, begin + duration),
+                }
+            }
+        }
+    }
+    // (begin, end) of every occurrence overlapping the days from..=to, including ones started on earlier days
+    fn occurrences(&self, from: NaiveDate, to: NaiveDate, holidays: &[Holiday]) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let (range_begin, range_end) = (from.and_hms(0, 0, 0), to.succ().and_hms(0, 0, 0));
+        let mut occurrences = Vec::new();
+        // one spare day either side for events in other time zones
+        let mut date = from - chrono::Duration::days(self.duration as i64 / 24 + 2);
+        while date <= to.succ() {
+            if self.starts_on(date, holidays) {
+                let (begin, end) = self.interval(date);
+                if end > range_begin && begin < range_end { occurrences.push((begin, end)) }
+            }
+            date = date.succ();
+        }
+        occurrences
+    }
+}
+
+// (column, token) pairs, whitespace separated, double quotes group words: Red 1 1 1 10 1 D 0 "Wake Up" none loc="Home office"
+fn tokenize(str: &str) -> Vec<(usize, String)> {
+    let mut tokens: Vec<(usize, String)> = Vec::new();
+    let (mut token, mut quoted, mut start) = ("".to_string(), false, None);
+    for (i, c) in str.chars().enumerate() {
+        if c.is_whitespace() && !quoted {
+            if let Some(column) = start { tokens.push((column, token)); token = "".to_string(); start = None }
+            continue
+        }
+        if start == None { start = Some(i+1) }
+        if c == '"' { quoted = !quoted } else { token.push(c) }
+    }
+    if let Some(column) = start { tokens.push((column, token)) }
+    tokens
+}
+
+// named, #rrggbb or 256-color @index
+fn parse_color(str: &str) -> Option<Color> {
+    if let Some(hex) = str.strip_prefix('#') {
+        if hex.len() != 6 { return None }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i+2)?, 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+    if let Some(index) = str.strip_prefix('@') { return index.parse::<u8>().ok().map(Color::Indexed) }
+    match str {
+        "Red" => Some(Color::Red), "LightRed" => Some(Color::LightRed), "Yellow" => Some(Color::Yellow), "LightYellow" => Some(Color::LightYellow),
+        "Green" => Some(Color::Green), "LightGreen" => Some(Color::LightGreen), "Blue" => Some(Color::Blue), "LightBlue" => Some(Color::LightBlue),
+        "Cyan" => Some(Color::Cyan), "LightCyan" => Some(Color::LightCyan), "Magenta" => Some(Color::Magenta), "LightMagenta" => Some(Color::LightMagenta),
+        "Black" => Some(Color::Black), "DarkGray" => Some(Color::DarkGray), "Gray" => Some(Color::Gray), "White" => Some(Color::White),
+        _ => None,
+    }
+}
+
+// xterm's default palette for the 16 named colors
+const NAMED_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)), (Color::Red, (205, 0, 0)), (Color::Green, (0, 205, 0)), (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)), (Color::Magenta, (205, 0, 205)), (Color::Cyan, (0, 205, 205)), (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)), (Color::LightRed, (255, 0, 0)), (Color::LightGreen, (0, 255, 0)), (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)), (Color::LightMagenta, (255, 0, 255)), (Color::LightCyan, (0, 255, 255)), (Color::White, (255, 255, 255)),
+];
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(i) if i < 16 => Some(NAMED_COLORS[i as usize].1),
+        Color::Indexed(i) if i < 232 => { let i = i - 16; Some((CUBE_LEVELS[(i/36) as usize], CUBE_LEVELS[(i/6%6) as usize], CUBE_LEVELS[(i%6) as usize])) }
+        Color::Indexed(i) => { let level = 8 + 10*(i - 232); Some((level, level, level)) }
+        _ => NAMED_COLORS.iter().find(|(c, _)| *c == color).map(|(_, rgb)| *rgb),
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let (dr, dg, db) = (a.0 as i32 - b.0 as i32, a.1 as i32 - b.1 as i32, a.2 as i32 - b.2 as i32);
+    dr*dr + dg*dg + db*db
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum ColorDepth { Ansi16, Ansi256, TrueColor }
+impl ColorDepth {
+    fn detect() -> ColorDepth {
+        let colorterm = std::env::var("COLORTERM").unwrap_or("".to_string());
+        if colorterm == "truecolor" || colorterm == "24bit" { ColorDepth::TrueColor }
+        else if std::env::var("TERM").unwrap_or("".to_string()).contains("256color") { ColorDepth::Ansi256 }
+        else { ColorDepth::Ansi16 }
+    }
+}
+
+// nearest color the terminal can show
+fn fit_color(color: Color, depth: ColorDepth) -> Color {
+    let target = match (color, depth) {
+        (Color::Rgb(..), ColorDepth::Ansi256) => {
+            let (r, g, b) = rgb(color).unwrap();
+            let level = |c: u8| CUBE_LEVELS.iter().enumerate().min_by_key(|(_, l)| (**l as i32 - c as i32).abs()).unwrap().0 as u8;
+            let cube = 16 + 36*level(r) + 6*level(g) + level(b);
+            let gray = 232 + (((r as u16 + g as u16 + b as u16) / 3).saturating_sub(3).min(230) / 10) as u8;
+            return [cube, gray].into_iter().min_by_key(|i| distance(rgb(Color::Indexed(*i)).unwrap(), (r, g, b))).map(Color::Indexed).unwrap()
+        }
+        (Color::Rgb(..), ColorDepth::Ansi16) | (Color::Indexed(_), ColorDepth::Ansi16) => rgb(color).unwrap(),
+        _ => return color,
+    };
+    NAMED_COLORS.iter().min_by_key(|(_, rgb)| distance(*rgb, target)).unwrap().0
+}
+
+// the token parse_color reads back as color
+fn color_name(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Indexed(i) => format!("@{}", i),
+        _ => format!("{:?}", color),
+    }
+}
+
+// black or white text, whichever reads better on the background
+fn foreground(background: Color) -> Color {
+    match rgb(background) {
+        Some((r, g, b)) if 0.2126*r as f32 + 0.7152*g as f32 + 0.0722*b as f32 > 127.5 => Color::Black,
+        _ => Color::White,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ParseError { file: String, line: usize, column: usize, message: String }
+impl Display for ParseError { fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    // line 0 when the whole file is at fault, column 0 when only the line is known
+    if self.line == 0 { write!(f, "{}: {}", self.file, self.message) }
+    else if self.column == 0 { write!(f, "{}:{}: {}", self.file, self.line, self.message) }
+    else { write!(f, "{}:{}:{}: {}", self.file, self.line, self.column, self.message) }
+} }
+
+#[derive(Debug, Clone)]
+struct Lint { file: String, line: usize, message: String }
+impl Display for Lint { fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.line == 0 { write!(f, "{}: {}", self.file, self.message) } else { write!(f, "{}:{}: {}", self.file, self.line, self.message) }
+} }
+
+fn lint_schedule(entries: &[Result<Event, ParseError>], from: NaiveDate, to: NaiveDate, holidays: &[Holiday]) -> Vec<Lint> {
+    let mut lints: Vec<Lint> = Vec::new();
+    let mut events: Vec<Event> = Vec::new();
+    let lint = |event: &Event, message: String| Lint{ file: event.file.clone(), line: event.line, message };
+    for entry in entries {
+        match entry {
+            Ok(event) => {
+                if event.color == Color::Reset { lints.push(lint(event, format!("{} has an unknown color", event.name))) }
+                if event.duration == 0 { lints.push(lint(event, format!("{} has zero length", event.name))) }
+                if !event.days.is_empty() && event.repeat_cycle != Cycle::Monthly && event.repeat_cycle != Cycle::Yearly {
+                    lints.push(lint(event, format!("{} has day patterns but repeats {}, they only apply to monthly and yearly events", event.name, event.repeat_cycle.name())))
+                }
+                if event.repeat_occurences != 0 && event.repeat_cycle != Cycle::Weekly {
+                    lints.push(lint(event, format!("{} has a weekday mask but repeats {}, it only applies to weekly events", event.name, event.repeat_cycle.name())))
+                }
+                events.push(event.clone());
+            }
+            Err(error) => lints.push(Lint{ file: error.file.clone(), line: error.line,
+                message: if error.column == 0 { error.message.clone() } else { format!("column {}: {}", error.column, error.message) } }),
+        }
+    }
+
+    let mut occurrences: Vec<(NaiveDateTime, NaiveDateTime, &Event)> = Vec::new();
+    for event in events.iter().filter(|e| e.duration > 0) {
+        for (begin, end) in event.occurrences(from, to, holidays) { occurrences.push((begin, end, event)) }
+    }
+    occurrences.sort_by_key(|o| o.0);
+    // (earlier line, later line, count, first overlap)
+    let mut overlaps: Vec<(&Event, &Event, usize, NaiveDateTime)> = Vec::new();
+    for (i, a) in occurrences.iter().enumerate() {
+        for b in occurrences[i+1..].iter() {
+            if b.0 >= a.1 { break }
+            let (first, second) = if (&a.2.file, a.2.line) <= (&b.2.file, b.2.line) { (a.2, b.2) } else { (b.2, a.2) };
+            match overlaps.iter_mut().find(|o| (&o.0.file, o.0.line) == (&first.file, first.line) && (&o.1.file, o.1.line) == (&second.file, second.line)) {
+                Some(overlap) => overlap.2 += 1,
+                None => overlaps.push((first, second, 1, b.0)),
+            }
+        }
+    }
+    for (first, second, count, at) in overlaps {
+        lints.push(lint(first, format!("{} overlaps {} ({}:{}) {} time{}, first at {}",
+            first.name, second.name, second.file, second.line, count, if count == 1 {""} else {"s"}, at.format("%Y-%m-%d %H:%M"))));
+    }
+    lints.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    lints
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Calendar { name: String, color: Color, file: String, visible: bool }
+
+struct CalendarSelection { calendars: Vec<Calendar>, state: ListState, show: bool }
+impl CalendarSelection {
+    fn new(calendars: Vec<Calendar>) -> CalendarSelection { CalendarSelection { calendars, state: ListState::default(), show: false } }
+    fn next(&mut self) {
+        let i = match self.state.selected() {
+            Some(i) => { if i >= self.calendars.len() - 1 { 0 } else { i + 1 } }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+    fn prev(&mut self) {
+        let i = match self.state.selected() {
+            Some(i) => { if i == 0 { self.calendars.len() - 1 } else { i - 1 } }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+    fn toggle(&mut self) { if let Some(i) = self.state.selected() { self.calendars[i].visible = !self.calendars[i].visible } }
+    fn visible(&self, sched_list: &[Event]) -> Vec<Event> {
+        sched_list.iter().filter(|e| self.calendars.iter().any(|c| c.name == e.calendar && c.visible)).cloned().collect()
+    }
+}
+
+// a named day from a holidays file, yearly ones fall on the same month and day every year
+#[derive(Debug, Clone, PartialEq)]
+struct Holiday { date: NaiveDate, name: String, yearly: bool }
+impl Holiday {
+    fn on(&self, date: NaiveDate) -> bool {
+        if self.yearly { date >= self.date && date.month() == self.date.month() && date.day() == self.date.day() }
+        else { date == self.date }
+    }
+}
+
+fn holiday(holidays: &[Holiday], date: NaiveDate) -> Option<&Holiday> { holidays.iter().find(|h| h.on(date)) }
+
+// "YYYY-MM-DD Name" or yearly "MM-DD Name" lines, or the all-day VEVENTs of an .ics file
+fn read_holidays(path: &str) -> Result<(Vec<Holiday>, Vec<ParseError>), Error> {
+    let text = std::fs::read_to_string(path)?;
+    let error = |line: usize, message: String| ParseError{ file: path.to_string(), line, column: 0, message };
+    let (mut holidays, mut errors) = (Vec::new(), Vec::new());
+    if !path.ends_with(".ics") {
+        for (i, line) in text.lines().enumerate() {
+            if line.starts_with("#") || line.trim().len() == 0 { continue }
+            let (date, name) = line.trim().split_once(char::is_whitespace).unwrap_or((line.trim(), ""));
+            let holiday = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                Ok(date) => Holiday { date, name: name.trim().to_string(), yearly: false },
+                // yearly dates go in year 4, a leap year, so 02-29 parses
+                Err(_) => match NaiveDate::parse_from_str(&format!("4-{}", date), "%Y-%m-%d") {
+                    Ok(date) => Holiday { date, name: name.trim().to_string(), yearly: true },
+                    Err(_) => { errors.push(error(i+1, format!("date '{}' is not YYYY-MM-DD or MM-DD", date))); continue }
+                }
+            };
+            holidays.push(holiday);
+        }
+        return Ok((holidays, errors))
     }
+    // unfold continuation lines, keeping the line each property started on
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        match (line.strip_prefix(' ').or(line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.1 += rest,
+            _ => lines.push((i+1, line.to_string())),
+        }
+    }
+    let (mut begin, mut date, mut name, mut yearly) = (0, None, "".to_string(), false);
+    for (i, line) in lines {
+        let (key, value) = line.split_once(':').unwrap_or((&line, ""));
+        match key.split(';').next().unwrap_or("") {
+            "BEGIN" if value == "VEVENT" => { begin = i; date = None; name = "".to_string(); yearly = false }
+            "DTSTART" => date = value.get(..8).and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok()),
+            "SUMMARY" => name = value.replace("\\,", ",").replace("\\;", ";").replace("\\n", " "),
+            "RRULE" => yearly = value.split(';').any(|rule| rule == "FREQ=YEARLY"),
+            "END" if value == "VEVENT" => match date {
+                Some(date) => holidays.push(Holiday { date, name: name.clone(), yearly }),
+                None => errors.push(error(begin, "event without a DTSTART date".to_string())),
+            }
+            _ => (),
+        }
+    }
+    Ok((holidays, errors))
+}
+
+struct Theme { title: Color, focus: Color, today: Color, selection: Color, holiday: Color }
+
+struct Config { secondary_timezone: Option<Tz>, notify_command: Vec<String>, color_depth: ColorDepth, theme: Theme, calendars: Vec<Calendar>,
+    holidays: Vec<String>, week_start: chrono::Weekday, week_numbers: bool, three_months: bool, block_color: String, pomodoro_work: i64, pomodoro_break: i64, pomodoro_task: bool, row_minutes: i64, progress_bar: bool }
+impl Config {
+    fn default() -> Config {
+        Config { secondary_timezone: None, notify_command: Vec::new(), color_depth: ColorDepth::detect(), calendars: Vec::new(), holidays: Vec::new(), week_start: locale_week_start(), week_numbers: false, three_months: false, block_color: "Cyan".to_string(),
+            pomodoro_work: 25, pomodoro_break: 5, pomodoro_task: false, row_minutes: 60, progress_bar: true,
+            theme: Theme { title: Color::Blue, focus: Color::Red, today: Color::Red, selection: Color::Yellow, holiday: Color::Green } }
+    }
+    // "key value" lines, a missing file keeps the defaults
+    fn from_file(path: &str) -> Config {
+        let mut config = Config::default();
+        for line in read_lines(path).unwrap_or(Vec::new()) {
+            if line.starts_with("#") { continue }
+            let (key, value) = match line.trim().split_once(char::is_whitespace) { Some((k, v)) => (k, v.trim()), None => continue };
+            match key {
+                "secondary_timezone" => config.secondary_timezone = value.parse::<Tz>().ok(),
+                "notify_command" => config.notify_command = value.split_whitespace().map(|s| s.to_string()).collect(),
+                "color_depth" => match value {
+                    "16" => config.color_depth = ColorDepth::Ansi16,
+                    "256" => config.color_depth = ColorDepth::Ansi256,
+                    "truecolor" => config.color_depth = ColorDepth::TrueColor,
+                    _ => (),
+                }
+                // calendar NAME PATH [COLOR]
+                "calendar" => {
+                    let fields: Vec<&str> = value.split_whitespace().collect();
+                    if fields.len() >= 2 {
+                        let color = fields.get(2).and_then(|c| parse_color(c)).unwrap_or(Color::Reset);
+                        config.calendars.push(Calendar { name: fields[0].to_string(), color, file: fields[1].to_string(), visible: true });
+                    }
+                }
+                "holidays" => config.holidays.push(value.to_string()),
+                "pomodoro_work" => if let Ok(minutes) = value.parse::<i64>() { config.pomodoro_work = minutes },
+                "pomodoro_break" => if let Ok(minutes) = value.parse::<i64>() { config.pomodoro_break = minutes },
+                "pomodoro_task" => config.pomodoro_task = value == "true",
+                "row_minutes" => if let Some(minutes) = value.parse::<i64>().ok().filter(|m| ROW_MINUTES.contains(m)) { config.row_minutes = minutes },
+                "progress_bar" => config.progress_bar = value == "true",
+                "week_start" => if let Ok(day) = value.parse::<chrono::Weekday>() { config.week_start = day },
+                "week_numbers" => config.week_numbers = value == "true",
+                "three_months" => config.three_months = value == "true",
+                "block_color" => if parse_color(value).is_some() { config.block_color = value.to_string() },
+                "theme_title" => if let Some(color) = parse_color(value) { config.theme.title = color },
+                "theme_focus" => if let Some(color) = parse_color(value) { config.theme.focus = color },
+                "theme_today" => if let Some(color) = parse_color(value) { config.theme.today = color },
+                "theme_selection" => if let Some(color) = parse_color(value) { config.theme.selection = color },
+                "theme_holiday" => if let Some(color) = parse_color(value) { config.theme.holiday = color },
+                _ => (),
+            }
+        }
+        let depth = config.color_depth;
+        let theme = &mut config.theme;
+        for color in [&mut theme.title, &mut theme.focus, &mut theme.today, &mut theme.selection, &mut theme.holiday] { *color = fit_color(*color, depth) }
+        config
+    }
+}
+
+// the notify command with the event name and start time appended, e.g. notify-send Lift 21:00
+fn notify_command(command: &[String], event: &Event, begin: NaiveDateTime) -> Command {
+    let mut notify = Command::new(&command[0]);
+    notify.args(&command[1..]).arg(&event.name).arg(begin.format("%H:%M").to_string());
+    notify
+}
+// runs it in the background with its output hidden, as the TUI needs
+fn notify(command: &[String], event: &Event, begin: NaiveDateTime) {
+    if command.is_empty() { return }
+    let child = notify_command(command, event, begin).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+    // reap it once it exits so it doesn't linger as a zombie
+    if let Ok(mut child) = child { thread::spawn(move || child.wait()); }
+}
+
+// reminders whose time falls in (after, until]
+fn due_reminders(sched_list: &[Event], after: NaiveDateTime, until: NaiveDateTime, holidays: &[Holiday]) -> Vec<(Event, NaiveDateTime)> {
+    let mut due = Vec::new();
+    for event in sched_list.iter().filter(|e| !e.reminders.is_empty()) {
+        // far enough ahead for the earliest reminder
+        let ahead = chrono::Duration::minutes(*event.reminders.iter().max().unwrap());
+        for (begin, _) in event.occurrences(after.date(), (until + ahead).date().succ(), holidays) {
+            if event.reminders.iter().any(|offset| { let at = begin - chrono::Duration::minutes(*offset); after < at && at <= until }) {
+                due.push((event.clone(), begin));
+            }
+        }
+    }
+    due
+}
+
+// a leading # is a comment unless it's a #rrggbb event color
+fn is_comment(line: &str) -> bool {
+    line.starts_with("#") && parse_color(line.split_whitespace().next().unwrap_or("")).is_none()
+}
+
+// schedule.toml takes over from the legacy column format once it exists
+fn schedule_path() -> &'static str { if std::path::Path::new("schedule.toml").exists() { "schedule.toml" } else { "schedule" } }
+
+// every event of a schedule file, or where and why it failed to parse. Events belong to the last
+// calendar in calendars unless a [name color] section, or a [[calendar]] table, says otherwise
+fn read_schedule(path: &str, calendars: &mut Vec<Calendar>) -> Result<Vec<Result<Event, ParseError>>, Error> {
+    let text = std::fs::read_to_string(path)?;
+    let error = |line: usize, column: usize, message: String| ParseError{ file: path.to_string(), line, column, message };
+    let default = calendars[calendars.len()-1].clone();
+    let mut add_calendar = |name: &str, color: Option<Color>| -> Calendar {
+        match calendars.iter().find(|c| c.name == name) {
+            Some(calendar) => calendar.clone(),
+            None => {
+                calendars.push(Calendar { name: name.to_string(), color: color.unwrap_or(default.color), file: path.to_string(), visible: true });
+                calendars[calendars.len()-1].clone()
+            }
+        }
+    };
+    let mut entries = Vec::new();
+    if !path.ends_with(".toml") {
+        let mut calendar = default.clone();
+        for (i, line) in text.lines().enumerate() {
+            if is_comment(line) || line.trim().len() == 0 { continue }
+            if let Some(section) = line.trim().strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let fields: Vec<&str> = section.split_whitespace().collect();
+                if fields.is_empty() { entries.push(Err(error(i+1, 1, "empty section".to_string()))); continue }
+                calendar = add_calendar(fields[0], fields.get(1).and_then(|c| parse_color(c)));
+                continue
+            }
+            entries.push(match Event::parse(line, i+1) {
+                Ok(mut event) => {
+                    if line.split_whitespace().next() == Some("-") { event.color = calendar.color }
+                    event.calendar = calendar.name.clone();
+                    event.file = path.to_string();
+                    Ok(event)
+                }
+                Err((column, message)) => Err(error(i+1, column, message)),
+            });
+        }
+        return Ok(entries)
+    }
+    let value = match text.parse::<toml::Value>() {
+        Ok(value) => value,
+        Err(e) => {
+            let (line, column) = e.line_col().map_or((0, 0), |(line, column)| (line+1, column+1));
+            return Ok(vec!(Err(error(line, column, e.to_string()))))
+        }
+    };
+    match value.get("version").and_then(|v| v.as_integer()) {
+        Some(1) => (),
+        Some(version) => return Ok(vec!(Err(error(1, 0, format!("unsupported version {}", version))))),
+        None => return Ok(vec!(Err(error(1, 0, "missing version".to_string())))),
+    }
+    for table in value.get("calendar").and_then(|v| v.as_array()).cloned().unwrap_or(Vec::new()) {
+        match table.get("name").and_then(|n| n.as_str()) {
+            Some(name) => { add_calendar(name, table.get("color").and_then(|c| c.as_str()).and_then(parse_color)); }
+            None => entries.push(Err(error(0, 0, "calendar without a name".to_string()))),
+        }
+    }
+    let lines: Vec<usize> = text.lines().enumerate().filter(|(_, line)| line.trim() == "[[event]]").map(|(i, _)| i+1).collect();
+    let tables = value.get("event").and_then(|v| v.as_array()).cloned().unwrap_or(Vec::new());
+    for (i, table) in tables.iter().enumerate() {
+        let line = lines.get(i).copied().unwrap_or(0);
+        entries.push(match Event::from_table(table, line) {
+            Ok(mut event) => {
+                if event.calendar == "" { event.calendar = default.name.clone() }
+                let calendar = add_calendar(&event.calendar, None);
+                if table.get("color").is_none() { event.color = calendar.color }
+                event.file = path.to_string();
+                Ok(event)
+            }
+            Err(message) => Err(error(line, 0, message)),
+        });
+    }
+    Ok(entries)
+}
+
+// the schedule file and any calendar and holiday files from the config, files that can't be read and holidays that don't parse are Err entries
+fn load_schedule(config: &Config) -> Result<(Vec<Calendar>, Vec<Holiday>, Vec<Result<Event, ParseError>>), Error> {
+    let path = schedule_path();
+    let mut calendars = vec!(Calendar { name: "schedule".to_string(), color: Color::Reset, file: path.to_string(), visible: true });
+    let mut entries = read_schedule(path, &mut calendars)?;
+    for calendar in &config.calendars {
+        calendars.retain(|c| c.name != calendar.name);
+        calendars.push(calendar.clone());
+        match read_schedule(&calendar.file, &mut calendars) {
+            Ok(read) => entries.extend(read),
+            Err(e) => entries.push(Err(ParseError { file: calendar.file.clone(), line: 0, column: 0, message: e.to_string() })),
+        }
+    }
+    let mut holidays = Vec::new();
+    for path in &config.holidays {
+        match read_holidays(path) {
+            Ok((mut read, errors)) => {
+                holidays.append(&mut read);
+                entries.extend(errors.into_iter().map(Err));
+            }
+            Err(e) => entries.push(Err(ParseError { file: path.clone(), line: 0, column: 0, message: e.to_string() })),
+        }
+    }
+    Ok((calendars, holidays, entries))
+}
+
+// appends a one-off event, like a time block, to the schedule file, returning it with the line it was written on
+fn append_block(path: &str, mut event: Event, color: &str) -> Result<Event, Error> {
+    let mut text = std::fs::read_to_string(path).unwrap_or("".to_string());
+    if !text.is_empty() && !text.ends_with('\n') { text += "\n" }
+    if path.ends_with(".toml") {
+        text += "\n";
+        event.line = text.lines().count() + 1;
+        text += &event.to_toml(color);
+    } else {
+        // leave any [section] so the block lands in the default calendar
+        let section = text.lines().filter_map(|l| l.trim().strip_prefix('[').and_then(|l| l.strip_suffix(']'))).last();
+        if section.map_or(false, |s| s.split_whitespace().next() != Some("schedule")) { text += "[schedule]\n" }
+        event.line = text.lines().count() + 1;
+        text += &(event.to_line(color) + "\n");
+    }
+    std::fs::write(path, text)?;
+    event.file = path.to_string();
+    event.calendar = "schedule".to_string();
+    Ok(event)
+}
+
+// skips events that don't parse, lint_schedule reports them
+fn parse_schedule(entries: &[Result<Event, ParseError>]) -> Vec<Event> {
+    entries.iter().filter_map(|entry| entry.as_ref().ok().cloned()).collect()
+}
+
+// a legacy column format file, path, to the [[event]] format, comments are kept and lines that don't parse are commented out
+fn migrate_schedule(path: &str, lines: &[String]) -> (String, Vec<Lint>) {
+    let mut out = "version = 1\n".to_string();
+    let mut lints = Vec::new();
+    let mut calendar = "".to_string();
+    for (i, line) in lines.iter().enumerate() {
+        if is_comment(line) { out += line; out += "\n"; continue }
+        if line.trim().len() == 0 { out += "\n"; continue }
+        if let Some(section) = line.trim().strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let fields: Vec<&str> = section.split_whitespace().collect();
+            if let Some(name) = fields.get(0) {
+                calendar = name.to_string();
+                out += &format!("\n[[calendar]]\nname = {}\n", toml::Value::String(calendar.clone()));
+                if let Some(color) = fields.get(1) { out += &format!("color = {}\n", toml::Value::String(color.to_string())) }
+            }
+            continue
+        }
+        match Event::parse(line, i+1) {
+            Ok(mut event) => {
+                event.calendar = calendar.clone();
+                out += "\n";
+                out += &event.to_toml(line.split_whitespace().next().unwrap_or(""));
+                let migrated = Event::from_table(&out.parse::<toml::Value>().unwrap()["event"].as_array().unwrap().last().unwrap(), i+1);
+                if migrated != Ok(event) { lints.push(Lint{ file: path.to_string(), line: i+1, message: "does not round trip".to_string() }) }
+            }
+            Err((_, message)) => {
+                out += &format!("# {}\n", line);
+                lints.push(Lint{ file: path.to_string(), line: i+1, message: format!("{}, commented out", message) });
+            }
+        }
+    }
+    (out, lints)
+}
+
+// what happened in the block of event that began at begin: done, skipped, or a task started or completed in it
+#[derive(Debug, Clone, PartialEq)]
+struct LogEntry { begin: NaiveDateTime, mark: String, event: String, task: String }
+impl LogEntry {
+    // 2026-10-19 09:00 completed "Code" 5b0f...
+    fn parse(line: &str) -> Result<LogEntry, (usize, String)> {
+        let tokens = tokenize(line);
+        if tokens.len() < 4 { return Err((line.chars().count()+1, format!("expected date, time, mark and event, found {} columns", tokens.len()))) }
+        let begin = NaiveDateTime::parse_from_str(&format!("{} {}", tokens[0].1, tokens[1].1), "%Y-%m-%d %H:%M")
+            .map_err(|_| (tokens[0].0, format!("'{} {}' is not YYYY-MM-DD HH:MM", tokens[0].1, tokens[1].1)))?;
+        Ok(LogEntry { begin, mark: tokens[2].1.clone(), event: tokens[3].1.clone(), task: tokens.get(4).map_or("".to_string(), |t| t.1.clone()) })
+    }
+    fn to_line(&self) -> String {
+        format!("{} {} \"{}\" {}", self.begin.format("%Y-%m-%d %H:%M"), self.mark, self.event.replace('"', "'"), self.task).trim_end().to_string()
+    }
+    // event names are written with ' for ", like Event::to_line does
+    fn is_for(&self, event: &Event) -> bool { self.event.replace('"', "'") == event.name.replace('"', "'") }
+}
+
+// a missing log is empty
+fn read_log(path: &str) -> (Vec<LogEntry>, Vec<ParseError>) {
+    let (mut log, mut errors) = (Vec::new(), Vec::new());
+    for (i, line) in read_lines(path).unwrap_or(Vec::new()).iter().enumerate() {
+        if line.starts_with("#") || line.trim().len() == 0 { continue }
+        match LogEntry::parse(line) {
+            Ok(entry) => log.push(entry),
+            Err((column, message)) => errors.push(ParseError{ file: path.to_string(), line: i+1, column, message }),
+        }
+    }
+    (log, errors)
+}
+
+fn append_log(path: &str, entry: &LogEntry) -> Result<(), Error> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", entry.to_line())
+}
+
+// the event occurring in begin..end and the start of that occurrence, task's own time block before any event around it
+fn block_in(sched_list: &[Event], begin: NaiveDateTime, end: NaiveDateTime, task: &str, holidays: &[Holiday]) -> Option<(Event, NaiveDateTime)> {
+    let blocks: Vec<(&Event, NaiveDateTime)> = sched_list.iter().filter_map(|e| e.occurrences(begin.date(), end.date(), holidays).into_iter()
+        .find(|(b, e)| *b < end && *e > begin).map(|(b, _)| (e, b))).collect();
+    blocks.iter().find(|(e, _)| task != "" && e.task == task).or(blocks.first()).map(|(e, b)| ((*e).clone(), *b))
+}
+
+// (event name, planned hours, logged hours) in the days from..=to, a block counts as logged when its last mark isn't skipped.
+// Pomodoros are logged per task rather than per block so they don't count
+fn planned_actual(sched_list: &[Event], log: &[LogEntry], from: NaiveDate, to: NaiveDate, holidays: &[Holiday]) -> Vec<(String, f64, f64)> {
+    let (range_begin, range_end) = (from.and_hms(0, 0, 0), to.succ().and_hms(0, 0, 0));
+    let mut rows: Vec<(String, f64, f64)> = Vec::new();
+    for event in sched_list {
+        for (begin, end) in event.occurrences(from, to, holidays) {
+            let hours = (end.min(range_end) - begin.max(range_begin)).num_minutes() as f64 / 60.0;
+            let logged = log.iter().rev().find(|l| l.is_for(event) && l.begin == begin && l.mark != "pomodoro").map_or(false, |l| l.mark != "skipped");
+            match rows.iter_mut().find(|r| r.0 == event.name) {
+                Some(row) => { row.1 += hours; if logged { row.2 += hours } }
+                None => rows.push((event.name.clone(), hours, if logged { hours } else { 0.0 })),
+            }
+        }
+    }
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+    rows
+}
+
+// hours per (event name, color, task modifier) in the days from..=to
+fn hours_report(sched_list: &[Event], from: NaiveDate, to: NaiveDate, holidays: &[Holiday]) -> Vec<(String, Color, String, f64)> {
+    let (range_begin, range_end) = (from.and_hms(0, 0, 0), to.succ().and_hms(0, 0, 0));
+    let mut rows: Vec<(String, Color, String, f64)> = Vec::new();
+    for event in sched_list {
+        let hours: f64 = event.occurrences(from, to, holidays).iter().map(|(begin, end)| (*end.min(&range_end) - *begin.max(&range_begin)).num_minutes() as f64 / 60.0).sum();
+        if hours == 0.0 { continue }
+        match rows.iter_mut().find(|r| r.0 == event.name && r.1 == event.color && r.2 == event.task_modifier) {
+            Some(row) => row.3 += hours,
+            None => rows.push((event.name.clone(), event.color, event.task_modifier.clone(), hours)),
+        }
+    }
+    rows.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap().then(a.0.cmp(&b.0)));
+    rows
+}
+
+fn report_csv(rows: &[(String, Color, String, f64)]) -> String {
+    let field = |s: &str| if s.contains(&[',', '"', '\n'][..]) { format!("\"{}\"", s.replace('"', "\"\"")) } else { s.to_string() };
+    let mut out = "name,color,context,hours\n".to_string();
+    for (name, color, context, hours) in rows { out += &format!("{},{},{},{:.2}\n", field(name), field(&color_name(*color)), field(context), hours) }
+    out
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum ReportGroup { Name, Color, Context }
+
+struct ReportSelection { monthly: bool, group: ReportGroup, week_start: chrono::Weekday }
+impl ReportSelection {
+    fn new(week_start: chrono::Weekday) -> ReportSelection { ReportSelection { monthly: false, group: ReportGroup::Name, week_start } }
+    fn next_group(&mut self) {
+        self.group = match self.group { ReportGroup::Name => ReportGroup::Color, ReportGroup::Color => ReportGroup::Context, ReportGroup::Context => ReportGroup::Name }
+    }
+    // the week or month around date
+    fn range(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        if self.monthly {
+            let first = date.with_day(1).unwrap();
+            let next = if first.month() == 12 { NaiveDate::from_ymd(first.year()+1, 1, 1) } else { NaiveDate::from_ymd(first.year(), first.month()+1, 1) };
+            (first, next.pred())
+        } else {
+            let first = week_begin(date, self.week_start);
+            (first, first + chrono::Duration::days(6))
+        }
+    }
+    // (label, bar color, hours) per group, largest first
+    fn bars(&self, rows: &[(String, Color, String, f64)]) -> Vec<(String, Color, f64)> {
+        let mut bars: Vec<(String, Color, f64)> = Vec::new();
+        for (name, color, context, hours) in rows {
+            let label = match self.group { ReportGroup::Name => name.clone(), ReportGroup::Color => color_name(*color), ReportGroup::Context => context.clone() };
+            match bars.iter_mut().find(|b| b.0 == label) {
+                Some(bar) => bar.2 += hours,
+                None => bars.push((label, if self.group == ReportGroup::Context { Color::Reset } else { *color }, *hours)),
+            }
+        }
+        bars.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap().then(a.0.cmp(&b.0)));
+        bars
+    }
+}
+
+// "2h, weekdays 9-18": a length in hours, then optionally weekdays, weekends or day names and an hour window
+#[derive(Debug, PartialEq, Clone)]
+struct SlotQuery { hours: u8, days: u8, from_hour: u32, to_hour: u32 }
+impl SlotQuery {
+    fn parse(str: &str) -> Result<SlotQuery, String> {
+        let mut query = SlotQuery { hours: 0, days: 0, from_hour: 0, to_hour: 24 };
+        for token in str.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+            let lower = token.to_lowercase();
+            if let Ok(hours) = lower.strip_suffix('h').unwrap_or(&lower).parse::<u8>() { query.hours = hours }
+            else if lower == "weekdays" { query.days |= 0b00111110 }
+            else if lower == "weekends" { query.days |= 0b01000001 }
+            else if let Some(day) = WEEKDAYS.iter().find(|d| d.to_lowercase() == lower) { query.days |= weekday_bit(day.parse().unwrap()) }
+            else if let Some((from, to)) = lower.split_once('-') {
+                match (from.parse::<u32>(), to.parse::<u32>()) {
+                    (Ok(from), Ok(to)) if from < to && to <= 24 => { query.from_hour = from; query.to_hour = to }
+                    _ => return Err(format!("'{}' is not an hour window like 9-18", token)),
+                }
+            }
+            else { return Err(format!("unknown '{}'", token)) }
+        }
+        if query.hours == 0 { return Err("missing a length such as 2h".to_string()) }
+        if query.days == 0 { query.days = 0b01111111 }
+        Ok(query)
+    }
+}
+
+// up to count gaps of query.hours in the four weeks after from that no event overlaps
+fn free_slots(sched_list: &[Event], query: &SlotQuery, from: NaiveDateTime, count: usize, holidays: &[Holiday]) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut begin = from.date().and_hms(from.hour(), 0, 0);
+    if begin < from { begin = begin + chrono::Duration::hours(1) }
+    let until = begin + chrono::Duration::days(28);
+    let occurrences: Vec<(NaiveDateTime, NaiveDateTime)> = sched_list.iter().flat_map(|e| e.occurrences(begin.date(), until.date(), holidays)).collect();
+    let mut slots = Vec::new();
+    while begin < until && slots.len() < count {
+        let end = begin + chrono::Duration::hours(query.hours as i64);
+        let fits = query.days & weekday_bit(begin.weekday()) != 0
+            && begin.hour() >= query.from_hour && (end - begin.date().and_hms(0, 0, 0)).num_hours() <= query.to_hour as i64
+            && !occurrences.iter().any(|(b, e)| *b < end && *e > begin);
+        if fits { slots.push((begin, end)); begin = end } else { begin = begin + chrono::Duration::hours(1) }
+    }
+    slots
+}
+
+struct SlotSelection { query: String, hours: u8, slots: Vec<(NaiveDateTime, NaiveDateTime)>, state: ListState }
+impl SlotSelection {
+    fn next(&mut self) {
+        let i = match self.state.selected() {
+            Some(i) => { if i >= self.slots.len() - 1 { 0 } else { i + 1 } }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+    fn prev(&mut self) {
+        let i = match self.state.selected() {
+            Some(i) => { if i == 0 { self.slots.len() - 1 } else { i - 1 } }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+    fn selected(&self) -> Option<(NaiveDateTime, NaiveDateTime)> { self.state.selected().map(|i| self.slots[i]) }
+}
+
+// a focus timer, work followed by a break, optionally tied to a task
+struct Pomodoro { task: Option<Task>, work: bool, began: NaiveDateTime, ends: NaiveDateTime }
+impl Pomodoro {
+    fn left(&self, now: NaiveDateTime) -> String {
+        let seconds = (self.ends - now).num_seconds().max(0);
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// the event occurring at now with its (begin, end), and the next event to begin after now with its begin
+fn now_and_next(sched_list: &[Event], now: NaiveDateTime, holidays: &[Holiday]) -> (Option<(Event, NaiveDateTime, NaiveDateTime)>, Option<(Event, NaiveDateTime)>) {
+    let occurrences: Vec<(&Event, NaiveDateTime, NaiveDateTime)> = sched_list.iter()
+        .flat_map(|e| e.occurrences(now.date(), now.date() + chrono::Duration::days(7), holidays).into_iter().map(move |(begin, end)| (e, begin, end)))
+        .collect();
+    let current = occurrences.iter().filter(|(_, begin, end)| *begin <= now && now < *end).min_by_key(|(_, _, end)| *end)
+        .map(|(e, begin, end)| ((*e).clone(), *begin, *end));
+    let next = occurrences.iter().filter(|(_, begin, _)| *begin > now).min_by_key(|(_, begin, _)| *begin).map(|(e, begin, _)| ((*e).clone(), *begin));
+    (current, next)
+}
+
+// 2d3h, 1h12m or 5m
+fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = (duration.num_seconds() + 59) / 60;
+    if minutes >= 24*60 { format!("{}d{}h", minutes / (24*60), minutes % (24*60) / 60) }
+    else if minutes >= 60 { format!("{}h{}m", minutes / 60, minutes % 60) }
+    else { format!("{}m", minutes) }
+}
+
+// minutes a schedule row can stand for, + and - step through them
+const ROW_MINUTES: [i64; 4] = [15, 30, 60, 120];
+
+// the hour on rows starting on the hour, the minutes on the others
+fn slot_label(slot_begin: NaiveDateTime) -> String {
+    if slot_begin.minute() == 0 { format!("{: >2}", slot_begin.hour()) } else { format!(":{:02}", slot_begin.minute()) }
 }
 
+// what the text typed into the prompt is for
+#[derive(Debug, PartialEq, Clone)]
+enum Prompt { FreeSlot, EventName(NaiveDateTime, u8), GoToWeek }
+
+fn read_lines(path: &str) -> Result<Vec<String>, Error> {
+    BufReader::new(File::open(path)?).lines().collect()
+}
 
 struct TaskSelection { items: Vec<Task>, state: ListState, project: String, tags: Vec<String> }
 impl TaskSelection {
@@ -252,7 +1258,8 @@ impl TaskSelection {
 }
 
 #[derive(Debug, Clone)]
-struct Task { uuid: String, id: u32, deps: Vec<String>, project: String, tags: Vec<String>, description: String, annotation: Vec<String>, urg: f32, status: String }
+struct Task { uuid: String, id: u32, deps: Vec<String>, project: String, tags: Vec<String>, description: String, annotation: Vec<String>, urg: f32, status: String,
+    due: Option<NaiveDateTime>, scheduled: Option<NaiveDateTime>, end: Option<NaiveDateTime> }
 // impl Display for Task { fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 //     // write!(f, "{}, {:?}, {}, {:?}, {}, {}, {}", self.id, self.deps, self.project, self.tags, self.description, self.urg, self.status)
 //     // write!(f, "{: >2}  ", self.id)?;
@@ -265,12 +1272,18 @@ impl Task {
     fn complete(&mut self) {
         println!("COMPLETING");
         let output = Command::new("task").args([&self.specifier(), "done"]).output().unwrap();
-        if output.status.success() { self.status = "completed".to_string(); }
+        if output.status.success() { self.status = "completed".to_string(); self.end = Some(Local::now().naive_local()) }
     }
     fn reopen(&mut self) {
         println!("REOPENING");
         let output = Command::new("task").args([&self.specifier(), "mod", "status:pending", "end:"]).output().unwrap();
-        if output.status.success() { self.status = "pending".to_string(); }
+        if output.status.success() { self.status = "pending".to_string(); self.end = None }
+    }
+    fn start(&mut self) -> bool {
+        Command::new("task").args([&self.specifier(), "start"]).output().map_or(false, |o| o.status.success())
+    }
+    fn stop(&mut self) -> bool {
+        Command::new("task").args([&self.specifier(), "stop"]).output().map_or(false, |o| o.status.success())
     }
     fn toggle_complete(&mut self) {
         if self.status == "pending" { self.complete() } else if self.status == "completed" { self.reopen() }
@@ -278,23 +1291,27 @@ impl Task {
 }
 
 #[derive(Clone, Debug)]
-struct Context {name: String, project: String, tags: Vec<String>, tasks: Vec<Task>, state: ListState, hidden: usize}
+struct Context {name: String, project: String, tags: Vec<String>, tasks: Vec<Task>, state: ListState, hidden: usize, task: String}
 impl Context {
-    fn new(name: String, project: String, tags: Vec<String>) -> Context { Context {name, project, tags, tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0 } }
-    fn general() -> Context { Context { name: "General".to_string(), project: "none".to_string(), tags: vec!("none".to_string()), tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0 } }
-    fn from_file(string: String) -> Context {
-        let substrings: Vec<String> = string.split(' ').map(|s| s.to_string()).collect();
+    fn new(name: String, project: String, tags: Vec<String>) -> Context { Context {name, project, tags, tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0, task: "".to_string() } }
+    fn general() -> Context { Context { name: "General".to_string(), project: "none".to_string(), tags: vec!("none".to_string()), tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0, task: "".to_string() } }
+    // Err is the column and message of the problem
+    fn from_file(string: String) -> Result<Context, (usize, String)> {
+        let substrings: Vec<String> = string.split_whitespace().map(|s| s.to_string()).collect();
         // for sub in &substrings {
             // println!("{}", sub);
         // }
-        Context { name: substrings[0].clone(), project: substrings[1].clone(), tags: substrings[2..].to_vec(), tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0  }
+        if substrings.len() < 2 { return Err((string.chars().count()+1, format!("expected a name and a project, found {} columns", substrings.len()))) }
+        Ok(Context { name: substrings[0].clone(), project: substrings[1].clone(), tags: substrings[2..].to_vec(), tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0, task: "".to_string() })
     }
     fn from_event(event: Event) -> Context {
         let substrings: Vec<String> = event.task_modifier.split(':').map(|s| s.to_string()).collect();
-        Context { name: event.name, project: substrings[0].clone(), tags: substrings[1..].to_vec(), tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0  }
+        Context { name: event.name, project: substrings[0].clone(), tags: substrings[1..].to_vec(), tasks: Vec::<Task>::new(), state: ListState::default(), hidden: 0, task: event.task }
     }
     fn populate(&mut self, tasks: Vec<Task>) {
         for task in tasks {
+            // a time block only shows its own task
+            if self.task != "" { if task.uuid == self.task { self.tasks.push(task) } continue }
             let mut tag_match = true;
             let mut project_match = false;
             for tag in &self.tags {
@@ -306,6 +1323,12 @@ impl Context {
         }
     }
     fn deselect(&mut self) { self.state.select(None); }
+    fn select_task(&mut self, uuid: &str) -> bool {
+        match self.tasks.iter().position(|t| t.uuid == uuid) {
+            Some(i) => { self.state.select(Some(i)); true }
+            None => false,
+        }
+    }
     fn selected(&mut self) -> Option<&mut Task> {
         match self.state.selected() {
             Some(i) => Some(&mut self.tasks[i]),
@@ -367,6 +1390,7 @@ impl ContextSelection {
                 if line.len() < 2 { continue }
                 let (mut uuid, mut id, mut deps, mut project, mut tags, mut description, mut annotation, mut urg, mut status)
                     = ("".to_string(), 0, Vec::<String>::new(), "".to_string(), Vec::<String>::new(), "".to_string(), Vec::<String>::new(), -1.0, "".to_string());
+                let (mut due, mut scheduled, mut end) = (None, None, None);
                 let re = Regex::new(r##"[^\{\},\[]*\[[^\]]*]|[^\{\},]+"##).unwrap();
                 let re_annotations = Regex::new(r"\\(.)").unwrap();
                 // let re = Regex::new(r"(?P<first>\w+)\s+(?P<second>\w+)").unwrap();
@@ -394,21 +1418,192 @@ impl ContextSelection {
                             annotation = out;
                         }
                         "status" => status = value,
+                        "due" => due = parse_task_date(&value),
+                        "scheduled" => scheduled = parse_task_date(&value),
+                        "end" => end = parse_task_date(&value),
                         "urgency" => urg = value.parse::<f32>().unwrap(),
                         &_ => (),
                     }
                 }
-                tasks.push(Task {uuid, id, deps, project, tags, description, annotation, urg, status});
+                tasks.push(Task {uuid, id, deps, project, tags, description, annotation, urg, status, due, scheduled, end});
             }
         }
         tasks
     }
 }
 
+// task export dates are UTC, e.g. 20221020T040000Z
+fn parse_task_date(value: &str) -> Option<NaiveDateTime> {
+    let utc = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ").ok()?;
+    Some(chrono::Utc.from_utc_datetime(&utc).with_timezone(&Local).naive_local())
+}
+
+// number of tasks completed on each day, by their end timestamps
+fn completions(tasks: &[Task]) -> HashMap<NaiveDate, usize> {
+    let mut counts = HashMap::new();
+    for task in tasks.iter().filter(|t| t.status == "completed") {
+        if let Some(end) = task.end { *counts.entry(end.date()).or_insert(0) += 1 }
+    }
+    counts
+}
+
+// heatmap shade for a day's completions: 1, 2-3, 4-5 and 6+
+fn heat(count: usize) -> Option<Color> {
+    match count {
+        0 => None,
+        1 => Some(Color::Rgb(0x0e, 0x44, 0x29)),
+        2..=3 => Some(Color::Rgb(0x00, 0x6d, 0x32)),
+        4..=5 => Some(Color::Rgb(0x26, 0xa6, 0x41)),
+        _ => Some(Color::Rgb(0x39, 0xd3, 0x53)),
+    }
+}
+
+#[derive(Clone)]
+enum AgendaEntry { Event(NaiveDateTime), Task(Task, NaiveDateTime) }
 
+struct AgendaSelection { days: i64, rows: Vec<(String, Option<AgendaEntry>)>, state: ListState }
+impl AgendaSelection {
+    fn new(days: i64) -> AgendaSelection { AgendaSelection { days, rows: Vec::new(), state: ListState::default() } }
+    fn populate(&mut self, from: NaiveDate, sched_list: &[Event], tasks: &[Task], holidays: &[Holiday]) {
+        self.rows = Vec::new();
+        for offset in 0..self.days {
+            let date = from + chrono::Duration::days(offset);
+            let mut entries: Vec<(NaiveDateTime, String, AgendaEntry)> = Vec::new();
+            for event in sched_list {
+                for (begin, end) in event.occurrences(date, date, holidays).into_iter().filter(|(begin, _)| begin.date() == date) {
+                    entries.push((begin, format!("  {}-{}  {}", begin.format("%H:%M"), end.format("%H:%M"), event.name), AgendaEntry::Event(begin)));
+                }
+            }
+            for task in tasks.iter().filter(|t| t.status == "pending") {
+                for (label, datetime) in [("due", task.due), ("scheduled", task.scheduled)] {
+                    if let Some(datetime) = datetime.filter(|d| d.date() == date) {
+                        entries.push((datetime, format!("  {} {}  {}", label, datetime.format("%H:%M"), task.description), AgendaEntry::Task(task.clone(), datetime)));
+                    }
+                }
+            }
+            if entries.is_empty() { continue }
+            entries.sort_by_key(|e| e.0);
+            self.rows.push((date.format("%a %b %d %Y").to_string(), None));
+            for (_, text, entry) in entries { self.rows.push((text, Some(entry))) }
+        }
+        match self.state.selected() {
+            Some(i) if i >= self.rows.len() => self.state.select(None),
+            _ => (),
+        }
+    }
+    fn selected(&self) -> Option<AgendaEntry> {
+        self.state.selected().and_then(|i| self.rows[i].1.clone())
+    }
+    fn next(&mut self) {
+        if self.rows.len() < 2 { return }
+        let mut i = self.state.selected().unwrap_or(0);
+        loop {
+            i = if i >= self.rows.len() - 1 { 0 } else { i + 1 };
+            if self.rows[i].1.is_some() { break }
+        }
+        self.state.select(Some(i));
+    }
+    fn prev(&mut self) {
+        if self.rows.len() < 2 { return }
+        let mut i = self.state.selected().unwrap_or(0);
+        loop {
+            i = if i == 0 { self.rows.len() - 1 } else { i - 1 };
+            if self.rows[i].1.is_some() { break }
+        }
+        self.state.select(Some(i));
+    }
+}
 
 
 fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "notify" {
+        // taskcalrs notify: fire the notify command for the next event, to try out the config
+        let now = Local::now().naive_local();
+        let (_, holidays, sched_entries) = load_schedule(&Config::from_file("config"))?;
+        let sched_list = parse_schedule(&sched_entries);
+        let next = sched_list.iter()
+            .flat_map(|e| e.occurrences(now.date(), now.date() + chrono::Duration::days(7), &holidays).into_iter().map(move |(begin, _)| (e, begin)))
+            .filter(|(_, begin)| *begin > now)
+            .min_by_key(|(_, begin)| *begin);
+        match next {
+            // in the foreground, so a stub script's output and exit status show
+            Some((event, begin)) => {
+                let command = Config::from_file("config").notify_command;
+                if command.is_empty() { println!("No notify_command in config"); std::process::exit(1) }
+                std::process::exit(notify_command(&command, event, begin).status()?.code().unwrap_or(1));
+            }
+            None => println!("No events in the next week"),
+        }
+        return Ok(())
+    }
+    if args.len() > 1 && args[1] == "lint" {
+        // taskcalrs lint [days] [from]
+        let days = args.get(2).and_then(|s| s.parse::<i64>().ok()).unwrap_or(28);
+        let from = args.get(3).and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()).unwrap_or(Local::today().naive_local());
+        let (_, holidays, sched_entries) = load_schedule(&Config::from_file("config"))?;
+        let lints = lint_schedule(&sched_entries, from, from + chrono::Duration::days(days-1), &holidays);
+        for lint in &lints { println!("{}", lint) }
+        std::process::exit(if lints.is_empty() { 0 } else { 1 });
+    }
+    if args.len() > 1 && args[1] == "report" {
+        // taskcalrs report [week|month] [date]: hours per event as CSV
+        let mut report = ReportSelection::new(Config::from_file("config").week_start);
+        report.monthly = args.get(2).map_or(false, |s| s == "month");
+        let date = args.get(3).and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()).unwrap_or(Local::today().naive_local());
+        let (from, to) = report.range(date);
+        let (_, holidays, sched_entries) = load_schedule(&Config::from_file("config"))?;
+        print!("{}", report_csv(&hours_report(&parse_schedule(&sched_entries), from, to, &holidays)));
+        return Ok(())
+    }
+    if args.len() > 1 && args[1] == "migrate" {
+        // taskcalrs migrate: schedule to schedule.toml, and calendar files from the config like work.schedule to work.toml
+        let mut files = vec!(("schedule".to_string(), "schedule.toml".to_string()));
+        for calendar in Config::from_file("config").calendars.iter().filter(|c| !c.file.ends_with(".toml")) {
+            files.push((calendar.file.clone(), std::path::Path::new(&calendar.file).with_extension("toml").to_string_lossy().to_string()));
+        }
+        let mut skipped = false;
+        for (path, target) in files {
+            if std::path::Path::new(&target).exists() { println!("{} already exists, {} was not migrated", target, path); skipped = true; continue }
+            let lines = match read_lines(&path) {
+                Ok(lines) => lines,
+                Err(e) => { println!("{}: {}", path, e); skipped = true; continue }
+            };
+            let (text, lints) = migrate_schedule(&path, &lines);
+            for lint in &lints { println!("{}", lint) }
+            std::fs::write(&target, text)?;
+            if path == "schedule" { println!("Wrote schedule.toml, schedule is no longer read while it exists") }
+            else { println!("Wrote {}, point its calendar line in config at it", target) }
+        }
+        std::process::exit(if skipped { 1 } else { 0 });
+    }
+
+    // Load files before raw mode so a failure leaves the terminal usable
+    let config = Config::from_file("config");
+    let (calendars, holidays, sched_entries) = load_schedule(&config)?;
+    let mut sched_list: Vec<Event> = parse_schedule(&sched_entries);
+    for event in sched_list.iter_mut() { event.color = fit_color(event.color, config.color_depth) }
+    let mut calendar_selection = CalendarSelection::new(calendars);
+    let mut visible_list: Vec<Event> = calendar_selection.visible(&sched_list);
+    let mut parse_errors: Vec<ParseError> = sched_entries.iter().filter_map(|e| e.as_ref().err().cloned()).collect();
+
+    let mut task_list: Vec<Task> = ContextSelection::from_cli();
+    let mut context_selection = ContextSelection::new();
+    let mut general_context = Context::general();
+    general_context.populate(task_list.clone());
+    context_selection.push(general_context);
+
+    for (i, line) in read_lines("contexts")?.iter().enumerate() {
+        if line.starts_with("#") || line.trim().len() == 0 { continue }
+        match Context::from_file(line.clone()) {
+            Ok(mut context) => { context.populate(task_list.clone()); context_selection.push(context) }
+            Err((column, message)) => parse_errors.push(ParseError{ file: "contexts".to_string(), line: i+1, column, message }),
+        }
+    }
+    let (mut time_log, log_errors) = read_log("timelog");
+    parse_errors.extend(log_errors);
+    let mut show_errors = !parse_errors.is_empty();
+
     // let stdin = stdin();
     let stdin = termion::async_stdin();
     let mut stdout = stdout().into_raw_mode()?;
@@ -439,28 +1634,30 @@ fn main() -> Result<(), Error> {
     let mut show_keys = false;
     let mut show_completed = true;
     let mut show_title = true;
+    let mut week_view = false;
+    let mut show_secondary = config.secondary_timezone.is_some();
+    let mut toast: Option<(String, NaiveDateTime)> = None;
+    // a task waiting to be placed in the schedule
+    let mut block_task: Option<Task> = None;
+    let mut prompt: Option<(Prompt, String)> = None;
+    let mut slot_selection: Option<SlotSelection> = None;
+    let mut pomodoro: Option<Pomodoro> = None;
+    let mut row_minutes = config.row_minutes;
+    let mut week_numbers = config.week_numbers;
+    let mut three_months = config.three_months;
+    let mut show_event = false;
+    let mut reminders_checked = Local::now().naive_local();
+    let mut agenda_selection = AgendaSelection::new(14);
     let mut today = Local::today().naive_local();
     let mut time = Local::now().time();
     let mut date_selection = DateSelection::datetime(today, time);
     let mut mode_selection = ModeSelection::new("Calendar".to_string(), Key::Null);
-    let mut sched_list: Vec<Event> = Vec::new();
-    let file = File::open("schedule")?;
-    for line in BufReader::new(file).lines().map(|l| l.unwrap()) {
-        if !line.starts_with("#") && line.len() > 0 { sched_list.push(Event::from_str(line)); }
-    }
-
-    let task_list: Vec<Task> = ContextSelection::from_cli();
-    let mut context_selection = ContextSelection::new();
-    let mut general_context = Context::general();
-    general_context.populate(task_list.clone());
-    context_selection.push(general_context);
-
-    let file = File::open("contexts")?;
-    for line in BufReader::new(file).lines().map(|l| l.unwrap()) {
-        let mut context = Context::from_file(line.clone());
-        context.populate(task_list.clone());
-        if !line.starts_with("#") && line.len() > 0 { context_selection.push(context); }
-    }
+    let mut show_lint = false;
+    let mut show_report = false;
+    let mut show_heatmap = false;
+    let mut show_completions = false;
+    let mut report_selection = ReportSelection::new(config.week_start);
+    let mut lints: Vec<Lint> = Vec::new();
 
 
 
@@ -469,6 +1666,37 @@ fn main() -> Result<(), Error> {
     loop {
         today = Local::today().naive_local();
         time = Local::now().time();
+        let now = today.and_time(time);
+        if now - reminders_checked >= chrono::Duration::seconds(1) {
+            for (event, begin) in due_reminders(&sched_list, reminders_checked, now, &holidays) {
+                print!("\x07");
+                std::io::stdout().flush()?;
+                toast = Some((format!("{} at {}", event.name, begin.format("%H:%M")), now));
+                notify(&config.notify_command, &event, begin);
+            }
+            reminders_checked = now;
+        }
+        if let Some(timer) = pomodoro.as_mut().filter(|p| now >= p.ends) {
+            print!("\x07");
+            std::io::stdout().flush()?;
+            if timer.work {
+                let task = timer.task.as_ref().map_or("".to_string(), |t| t.uuid.clone());
+                let event = block_in(&sched_list, timer.began, timer.began + chrono::Duration::minutes(1), &task, &holidays).map_or("none".to_string(), |(e, _)| e.name);
+                let entry = LogEntry { begin: timer.began, mark: "pomodoro".to_string(), event, task };
+                toast = Some((match append_log("timelog", &entry) {
+                    Ok(_) => format!("Break for {} minutes", config.pomodoro_break),
+                    Err(e) => format!("Could not write timelog: {}", e),
+                }, now));
+                time_log.push(entry);
+                if config.pomodoro_task { if let Some(task) = timer.task.as_mut() { task.stop(); } }
+                timer.work = false;
+                timer.ends = now + chrono::Duration::minutes(config.pomodoro_break);
+            } else {
+                toast = Some(("Break over".to_string(), now));
+                pomodoro = None;
+            }
+        }
+        if toast.as_ref().map_or(false, |(_, shown)| now - *shown > chrono::Duration::seconds(10)) { toast = None }
         terminal.draw(|f| {
             if show_title {
                 let (foreground, background) = create_title(f.size());
@@ -477,142 +1705,270 @@ fn main() -> Result<(), Error> {
                 f.render_widget(foreground, rect);
             } else {
 
-                let rects = create_rects(f.size(), show_keys);
+                let months = if three_months && f.size().width >= calendar_width(3, week_numbers) + 60 { 3 } else { 1 };
+                let rects = create_rects(f.size(), show_keys, week_numbers, months);
+                let completed = if show_heatmap || show_completions { completions(&task_list) } else { HashMap::new() };
+                let heat_color = |date: &NaiveDate| if show_heatmap { heat(*completed.get(date).unwrap_or(&0)).map(|c| fit_color(c, config.color_depth)) } else { None };
 
                 // Calendar
-                let cal_title = date_selection.month_string()+" "+&date_selection.day().to_string()+" "+&date_selection.date.year().to_string();
+                let mut cal_title = date_selection.month_string()+" "+&date_selection.day().to_string()+" "+&date_selection.date.year().to_string();
+                if let Some(holiday) = holiday(&holidays, date_selection.date) { cal_title += &format!(" - {}", holiday.name) }
+                if show_heatmap { cal_title += &format!(" - {} done", completed.get(&date_selection.date).unwrap_or(&0)) }
                 let calendar = Block::default()
-                    .title(Spans::from(vec![ Span::styled(cal_title, Style::default().fg(Color::Blue)), ]))
+                    .title(Spans::from(vec![ Span::styled(cal_title, Style::default().fg(config.theme.title)), ]))
                     .title_alignment(Alignment::Center)
-                    .border_style(Style::default().fg( if mode_selection.mode == "Calendar".to_string() {Color::Red} else {Color::White}))
+                    .border_style(Style::default().fg( if mode_selection.mode == "Calendar".to_string() {config.theme.focus} else {Color::White}))
                     .borders(Borders::ALL);
-                let date_nums = create_dates(&date_selection);
+                f.render_widget(calendar.clone(), rects[1]);
                 let mut dates = Vec::new();
-                let cal_rects = create_cal_rects(calendar.inner(rects[1]), show_keys);
-                for (i, (rect, date)) in cal_rects.clone().into_iter().zip(date_nums.iter()).enumerate() {
-                    dates.push((
-                        Block::default()
-                            .title(
-                                Span::styled(
-                                    if i < 7 { date.weekday().to_string() }
-                                    else { format!("{: ^4}", format!("{: >2}", date.day().to_string())) },
-                                    Style::default().fg(
-                                        if i < 7 { Color::Blue }
-                                        else if date.month() == date_selection.month().into() { Color::White }
-                                        else { Color::Red }
-                                    ).bg(
-                                        if date == &today { Color::Red }
-                                        else if date == &date_selection.date { Color::Yellow }
-                                        else { Color::Reset }
+                let (cal_rects, week_rects) = create_cal_rects(calendar.inner(rects[1]), show_keys, week_numbers, months as usize);
+                // the selected month, between its neighbours when there are three
+                let mut shown = vec!(DateSelection::date(date_selection.first()));
+                if months == 3 {
+                    let (mut prev, mut next) = (shown[0].clone(), shown[0].clone());
+                    prev.prev_month();
+                    next.next_month();
+                    shown = vec!(prev, shown.remove(0), next);
+                }
+                for (m, month) in shown.iter().enumerate() {
+                    let date_nums = create_dates(month, config.week_start);
+                    // neighbours get their name on the border, the selected month has the title
+                    if m != months as usize / 2 {
+                        let width = calendar_width(1, week_numbers) - 2;
+                        let rect = Rect { x: rects[1].x + 1 + m as u16 * (width + 2), y: rects[1].y, width, height: 1 };
+                        f.render_widget(Paragraph::new(format!(" {} ", month.date.format("%B %Y"))).alignment(Alignment::Center).style(Style::default().fg(config.theme.title)), rect);
+                    }
+                    // ISO 8601 week of each row's Monday, whichever day rows start on
+                    for (row, rect) in week_rects.iter().skip(m*7).take(7).enumerate().skip(1) {
+                        let week = &date_nums[row*7..row*7+7];
+                        if months > 1 && !week.iter().any(|d| d.month() == month.date.month()) { continue }
+                        let monday = week.iter().find(|d| d.weekday() == chrono::Weekday::Mon).unwrap();
+                        f.render_widget(Paragraph::new(format!("{: >2}", monday.iso_week().week())).style(Style::default().fg(Color::DarkGray)), *rect);
+                    }
+                    for (i, (rect, date)) in cal_rects[m*49..m*49+49].iter().zip(date_nums.iter()).enumerate() {
+                        // side by side, each month shows only its own days
+                        if months > 1 && i >= 7 && date.month() != month.date.month() { continue }
+                        let heat = if i < 7 || date == &today || date == &date_selection.date { None } else { heat_color(date) };
+                        dates.push((
+                            Block::default()
+                                .title(
+                                    Span::styled(
+                                        if i < 7 { date.weekday().to_string() }
+                                        else { format!("{: ^4}", format!("{: >2}", date.day().to_string())) },
+                                        Style::default().fg(
+                                            if i < 7 { config.theme.title }
+                                            else if let Some(color) = heat { foreground(color) }
+                                            else if holiday(&holidays, *date).is_some() { config.theme.holiday }
+                                            else if date.month() == month.date.month() { Color::White }
+                                            else { Color::Red }
+                                        ).bg(
+                                            if date == &today { config.theme.today }
+                                            else if date == &date_selection.date { config.theme.selection }
+                                            else { heat.unwrap_or(Color::Reset) }
+                                        ).add_modifier(
+                                            if i >= 7 && notable_day(&visible_list, *date, &holidays) { Modifier::UNDERLINED }
+                                            else { Modifier::empty() }
+                                        )
                                     )
-                                )
-                            ),
-                    rect));
+                                ),
+                        *rect));
+                    }
                 }
-                f.render_widget(calendar, rects[1]);
                 for date in dates { f.render_widget(date.0, date.1); }
                 if show_keys {
                     f.render_widget(
-                        Paragraph::new("H-L:yr J-K:mn h-l:wk j-k:dy")
+                        Paragraph::new("H-L:yr J-K:mn h-l:wk j-k:dy a:heat c:done m:months")
                         .block(Block::default()
                         .borders(Borders::TOP)
-                        .border_style(Style::default().fg( if mode_selection.mode == "Calendar".to_string() {Color::Red} else {Color::White}))
+                        .border_style(Style::default().fg( if mode_selection.mode == "Calendar".to_string() {config.theme.focus} else {Color::White}))
                     ), cal_rects[cal_rects.len()-1]); }
 
 
                 // Schedule
                 // Outer Schedule Block
+                let (current, next) = now_and_next(&visible_list, today.and_time(time), &holidays);
+                let schedule_title = match &block_task {
+                    Some(task) => format!("Place {}: enter:here esc:cancel", task.description),
+                    None => match &pomodoro {
+                        Some(timer) => {
+                            let now = today.and_time(time);
+                            match &timer.task {
+                                Some(task) => format!("{} {} {} ({} today)", if timer.work { "Focus" } else { "Break" }, timer.left(now), task.description,
+                                    time_log.iter().filter(|l| l.mark == "pomodoro" && l.task == task.uuid && l.begin.date() == today).count()),
+                                None => format!("{} {}", if timer.work { "Focus" } else { "Break" }, timer.left(now)),
+                            }
+                        }
+                        None => {
+                            let now = today.and_time(time);
+                            let mut title = format!("{}", time.format("%H:%M:%S"));
+                            if let Some((event, _, end)) = &current { title += &format!(" · {}: {} left", event.name, format_duration(*end - now)) }
+                            if let Some((event, begin)) = &next { title += &format!(" · next: {} in {}", event.name, format_duration(*begin - now)) }
+                            title
+                        }
+                    }
+                };
                 let schedule = Block::default()
-                    .title(Spans::from(vec![ Span::styled(format!("{}", time.format("%H:%M:%S")), Style::default().fg(Color::Blue)), ]))
+                    .title(Spans::from(vec![ Span::styled(schedule_title, Style::default().fg(config.theme.title)), ]))
                     .title_alignment(Alignment::Center)
-                    .border_style(Style::default().fg( if mode_selection.mode == "Schedule".to_string() {Color::Red} else {Color::White}))
+                    .border_style(Style::default().fg( if mode_selection.mode == "Schedule".to_string() {config.theme.focus} else {Color::White}))
                     .borders(Borders::ALL);
                 // let colors = [Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::LightBlue, Color::Blue, Color::Magenta];
 
-                // Inner Schedule Block
-                let sched_rects = create_sched_rects(schedule.inner(rects[2]), show_keys);
-                let left_rects = sched_rects.0;
-                let right_rects = sched_rects.1;
-                for (i, rect) in left_rects.iter().enumerate() {
-                    if show_keys && i == left_rects.len()-1 {
-                        f.render_widget(
-                            Paragraph::new("H-L:mn J-K:wk h-l:dy j-k:hr")
-                            .block(Block::default()
-                                .borders(Borders::TOP)
-                                .border_style(Style::default().fg( if mode_selection.mode == "Schedule".to_string() {Color::Red} else {Color::White}))
-                        ), left_rects[left_rects.len()-1]);
-                    } else {
-                        let hour = (date_selection.hour()+i as u32)%24;
-                        // println!("{}, {}", time.format("%H").to_string(), format!("{:0>2}", hour.to_string()));
-                        let mut style = Style::default() .bg(
-                                            if time.format("%H").to_string() == format!("{:0>2}", hour.to_string()) {
-                                                if date_selection.date == today.pred() && (date_selection.hour()+i as u32) >= 24 {Color::Red}
-                                                else if date_selection.date == today && (date_selection.hour()+i as u32) < 24 {Color::Red}
-                                                else {Color::Reset}
-                                            }
-                                            else {Color::Reset});
-                        // if hour % 2 == 0 { style = style.add_modifier(Modifier::BOLD); }
-                        let widget = Paragraph::new(format!("{: >2}", hour.to_string())).block(Block::default().style(style));
-                        f.render_widget(widget, *rect);
+                // Current Event
+                let row = chrono::Duration::minutes(row_minutes);
+                let sel_begin = date_selection.slot(row_minutes);
+                let sel_end = sel_begin + row;
+                match visible_list.iter().find(|e| e.occurrences(sel_begin.date(), sel_begin.date(), &holidays).iter().any(|(begin, end)| *begin < sel_end && *end > sel_begin)) {
+                    Some(event) => if date_selection.event() != Some(event.clone()) {
+                        date_selection.set_event(Some(event.clone()));
+                        let mut context = Context::from_event(event.clone());
+                        context.populate(task_list.clone());
+                        context_selection.contexts[0] = context;
+                    }
+                    None => if context_selection.contexts[0].name != "General" {
+                        date_selection.set_event(None);
+                        let mut context = Context::general();
+                        context.populate(task_list.clone());
+                        context_selection.contexts[0] = context;
                     }
                 }
-                // let colors = [Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::LightBlue, Color::Blue, Color::Magenta];
-                let mut event_is_set = false;
-                for event in sched_list.iter() {
-                    let mut datetime = date_selection.clone();
-                    let begin_time = event.time.format("%H").to_string().parse::<u32>().unwrap();
-                    let end_time = (begin_time + event.duration as u32) % 24;
-                    for (i, rect) in right_rects.iter().enumerate() {
-                        let mut render = false;
-                        match event.repeat_cycle {
-                            Cycle::Never => { if begin_time <= datetime.hour() && datetime.hour() < end_time && datetime.date == event.date { render = true; } }
-                            Cycle::Daily => {
-                                if begin_time < end_time {
-                                    if begin_time <= datetime.hour() && datetime.hour() < end_time { render = true; }
-                                } else {
-                                    if begin_time <= datetime.hour() && datetime.hour() > end_time { render = true; }
-                                    else if begin_time >= datetime.hour() && datetime.hour() < end_time { render = true; }
+
+                // Inner Schedule Block
+                if week_view {
+                    let now = today.and_time(time);
+                    let week_begin = week_begin(date_selection.date, config.week_start);
+                    let (left_rects, day_rects) = create_week_rects(schedule.inner(rects[2]), show_keys);
+                    for (j, rect) in day_rects[0].iter().enumerate() {
+                        let date = week_begin + chrono::Duration::days(j as i64);
+                        f.render_widget(Paragraph::new(format!("{: ^width$}", format!("{} {}", date.weekday(), date.day()), width=rect.width as usize))
+                            .style(Style::default().fg(config.theme.title).bg(
+                                if date == today { config.theme.today }
+                                else if date == date_selection.date { config.theme.selection }
+                                else { Color::Reset })), *rect);
+                    }
+                    for (i, rect) in left_rects.iter().enumerate() {
+                        if show_keys && i == left_rects.len()-1 {
+                            f.render_widget(
+                                Paragraph::new("H-L:mn J-K:wk h-l:dy j-k:row +/-:zoom w:day")
+                                .block(Block::default()
+                                    .borders(Borders::TOP)
+                                    .border_style(Style::default().fg( if mode_selection.mode == "Schedule".to_string() {config.theme.focus} else {Color::White}))
+                            ), *rect);
+                        } else if i > 0 {
+                            let slot_begin = week_begin.and_time(sel_begin.time()) + row * (i as i32 - 1);
+                            let is_now = (0..7).any(|j| {
+                                let begin = slot_begin + chrono::Duration::days(j);
+                                begin <= now && now < begin + row
+                            });
+                            f.render_widget(Paragraph::new(slot_label(slot_begin))
+                                .style(Style::default().bg(if is_now { config.theme.today } else { Color::Reset })), *rect);
+                        }
+                    }
+                    for (j, _) in day_rects[0].iter().enumerate() {
+                        let date = week_begin + chrono::Duration::days(j as i64);
+                        let view_begin = date.and_time(sel_begin.time());
+                        let view_end = view_begin + row * day_rects.len() as i32;
+                        let occurrences: Vec<(&Event, NaiveDateTime, NaiveDateTime)> = visible_list.iter()
+                            .flat_map(|e| e.occurrences(date, view_end.date(), &holidays).into_iter().map(move |(begin, end)| (e, begin, end)))
+                            .collect();
+                        for (i, cells) in day_rects[1..].iter().enumerate() {
+                            let rect = cells[j];
+                            let slot_begin = view_begin + row * i as i32;
+                            let slot_end = slot_begin + row;
+                            let mut style = Style::default();
+                            let mut text = "".to_string();
+                            let mut overlaps: Vec<(&Event, NaiveDateTime, NaiveDateTime)> = occurrences.iter().copied().filter(|(_, begin, end)| *begin < slot_end && *end > slot_begin).collect();
+                            overlaps.sort_by_key(|(_, begin, _)| *begin);
+                            // the first event starting in the cell names it, with a count of the others overlapping it, or just + in narrow cells
+                            let named = overlaps.iter().find(|(_, begin, _)| *begin >= slot_begin || i == 0);
+                            if let Some((event, _, _)) = named.or(overlaps.first()) {
+                                style = style.bg(event.color).fg(foreground(event.color));
+                                let name = if event.is_done(&task_list) { style = style.add_modifier(Modifier::CROSSED_OUT); format!("✓ {}", event.name) } else { event.name.clone() };
+                                if named.is_some() {
+                                    let more = if overlaps.len() < 2 { "".to_string() } else if rect.width < 8 { "+".to_string() } else { format!(" +{}", overlaps.len() - 1) };
+                                    let width = (rect.width as usize).saturating_sub(more.chars().count());
+                                    text = name.chars().take(width).collect::<String>() + &more;
                                 }
                             }
-                            Cycle::Weekly => {
-                                // if ("1".to_string() + &"0".repeat((6-date_selection.date.weekday().num_days_from_sunday()) as usize)).as_bytes().iter().fold(0, |acc, &b| acc*2 + b - 48 as u8) & event.repeat_occurences != 0 {
-                                if 2_u8.pow(6-date_selection.date.weekday().num_days_from_sunday()) & event.repeat_occurences != 0 {
-                                    if begin_time <= datetime.hour() && datetime.hour() < end_time { render = true; }
-                                }
+                            if slot_begin <= now && now < slot_end {
+                                style = style.fg(config.theme.today);
+                                text = format!("{:─<width$}", text, width=rect.width as usize);
                             }
-                            _ => (),
+                            f.render_widget(Paragraph::new(text).style(style), rect);
                         }
-                        if render {
-                            if i==0 {
-                                event_is_set = true;
-                                if date_selection.event() == None || date_selection.event().unwrap() != event.to_owned() {
-                                    date_selection.set_event(Some(event.clone()));
-                                    let mut context = Context::from_event(event.clone());
-                                    context.populate(task_list.clone());
-                                    context_selection.contexts[0] = context;
-                                }
-                            }
-                            let widget = Paragraph::new(if datetime.hour() == begin_time
-                                                        || ( begin_time < end_time && begin_time <= date_selection.hour() && date_selection.hour() < end_time && datetime.hour() == date_selection.hour())
-                                                        || ( begin_time > end_time
-                                                             && ((begin_time <= datetime.hour() && datetime.hour() > end_time && datetime.hour() == date_selection.hour())
-                                                                 || (begin_time >= datetime.hour() && datetime.hour() < end_time && datetime.hour() == date_selection.hour())
-                                                                 ))
-                                                        { format!("{: ^width$}", event.name.clone(), width=rect.width as usize) } else { "".to_string() })
+                    }
+                } else {
+                    let sched_rects = create_sched_rects(schedule.inner(rects[2]), show_keys, show_secondary, if row_minutes < 60 { 1 } else { 2 });
+                    let left_rects = sched_rects.0;
+                    let right_rects = sched_rects.2;
+                    if let Some(tz) = config.secondary_timezone.filter(|_| show_secondary) {
+                        for (i, rect) in sched_rects.1.iter().enumerate() {
+                            let slot_begin = sel_begin + row * i as i32;
+                            let label = match Local.from_local_datetime(&slot_begin).earliest() {
+                                Some(datetime) => datetime.with_timezone(&tz).format("%H:%M").to_string(),
+                                None => "".to_string(),
+                            };
+                            f.render_widget(Paragraph::new(format!("{: >5}", label)).style(Style::default().fg(Color::DarkGray)), *rect);
+                        }
+                    }
+                    for (i, rect) in left_rects.iter().enumerate() {
+                        if show_keys && i == left_rects.len()-1 {
+                            f.render_widget(
+                                Paragraph::new("H-L:mn J-K:wk h-l:dy j-k:row +/-:zoom w:week z:zone v:cals d/x:done/skip f:free enter:event")
                                 .block(Block::default()
-                                    .style(Style::default()
-                                        .bg(event.color)
-                                        .fg(if event.color == Color::White { Color::Black } else { Color::White })));
+                                    .borders(Borders::TOP)
+                                    .border_style(Style::default().fg( if mode_selection.mode == "Schedule".to_string() {config.theme.focus} else {Color::White}))
+                            ), left_rects[left_rects.len()-1]);
+                        } else {
+                            let slot_begin = sel_begin + row * i as i32;
+                            let now = today.and_time(time);
+                            let style = Style::default().bg(if slot_begin <= now && now < slot_begin + row { config.theme.today } else { Color::Reset });
+                            // if hour % 2 == 0 { style = style.add_modifier(Modifier::BOLD); }
+                            let widget = Paragraph::new(slot_label(slot_begin)).block(Block::default().style(style));
                             f.render_widget(widget, *rect);
                         }
-                        datetime.next_hour()
                     }
-                }
-                if !event_is_set && context_selection.contexts[0].name != "General" {
-                    date_selection.set_event(None);
-                    let mut context = Context::general();
-                    context.populate(task_list.clone());
-                    context_selection.contexts[0] = context;
+                    // let colors = [Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::LightBlue, Color::Blue, Color::Magenta];
+                    let view_begin = sel_begin;
+                    let view_end = view_begin + row * right_rects.len() as i32;
+                    let occurrences: Vec<(&Event, NaiveDateTime, NaiveDateTime)> = visible_list.iter()
+                        .flat_map(|e| e.occurrences(view_begin.date(), view_end.date(), &holidays).into_iter().map(move |(begin, end)| (e, begin, end)))
+                        .collect();
+                    for (i, rect) in right_rects.iter().enumerate() {
+                        let slot_begin = view_begin + row * i as i32;
+                        let slot_end = slot_begin + row;
+                        let mut overlaps: Vec<(&Event, NaiveDateTime, NaiveDateTime)> = occurrences.iter().copied().filter(|(_, begin, end)| *begin < slot_end && *end > slot_begin).collect();
+                        if overlaps.is_empty() { continue }
+                        overlaps.sort_by_key(|(_, begin, _)| *begin);
+                        // every event starting in the row is named in its own color, e.g. Wake Up/Code
+                        let named: Vec<&Event> = overlaps.iter().filter(|(_, begin, _)| *begin >= slot_begin || i == 0).map(|(e, _, _)| *e).collect();
+                        let mut spans = Vec::new();
+                        for (n, event) in named.iter().enumerate() {
+                            if n > 0 { spans.push(Span::raw("/")) }
+                            let done = event.is_done(&task_list);
+                            spans.push(Span::styled(if done { format!("✓ {}", event.name) } else { event.name.clone() },
+                                Style::default()
+                                    .bg(event.color)
+                                    .fg(foreground(event.color))
+                                    .add_modifier(if done { Modifier::CROSSED_OUT } else { Modifier::empty() })));
+                        }
+                        let color = named.first().unwrap_or(&overlaps[0].0).color;
+                        let widget = Paragraph::new(Spans::from(spans))
+                            .alignment(Alignment::Center)
+                            .block(Block::default().style(Style::default().bg(color).fg(foreground(color))));
+                        f.render_widget(widget, *rect);
+                        // progress through the current event on the bottom line of the row holding now, unless that would hide the names
+                        let now = today.and_time(time);
+                        if let Some((event, current_begin, current_end)) = current.as_ref()
+                            .filter(|(e, b, _)| config.progress_bar && overlaps.iter().any(|(o, ob, _)| *o == e && ob == b) && slot_begin <= now && now < slot_end && (rect.height > 1 || named.is_empty())) {
+                            let fraction = (now - *current_begin).num_seconds() as f64 / (*current_end - *current_begin).num_seconds() as f64;
+                            let width = (rect.width as usize).saturating_sub(5);
+                            let filled = (fraction * width as f64).round() as usize;
+                            let bar = format!("{}{}{: >4}%", "━".repeat(filled), "─".repeat(width - filled), (fraction * 100.0) as u32);
+                            let line = Rect { y: rect.y + rect.height - 1, height: 1, ..*rect };
+                            f.render_widget(Paragraph::new(bar).style(Style::default().bg(event.color).fg(foreground(event.color))), line);
+                        }
+                    }
                 }
                 f.render_widget(schedule, rects[2]);
 
@@ -627,58 +1983,312 @@ fn main() -> Result<(), Error> {
                 // f.render_stateful_widget(task_list, rects[0], &mut task_selection.state);
 
 
-                let contexts = Block::default()
-                    .title(Spans::from(vec![ Span::styled("Contexts", Style::default().fg(Color::Blue)), ]))
-                    .title_alignment(Alignment::Center)
-                    .border_style(Style::default().fg( if mode_selection.mode == "Contexts".to_string() {Color::Red} else {Color::White}))
-                    .borders(Borders::ALL);
-                // for rect in create_context_rects(contexts.inner(rects[0]), show_keys, context_list.len()+1) {
-                let mut context_rects = create_context_rects(contexts.inner(rects[0]), show_keys, context_selection.len());
-                // let extra_rect = context_rects[0];
-                if context_selection.len() % 2 == 1 {
-                    context_rects[0] = context_rects[0].union(context_rects[1]);
-                    context_rects.remove(1);
-                }
-                // let rects = create_context_rects(contexts.inner(rects[0]), show_keys, context_list.len()+1);
-                // println!("{}", context_selection.len());
-                for (i, rect) in context_rects.iter().enumerate() {
-                    if show_keys && i == context_rects.len()-1 {
+                if mode_selection.mode == "Year".to_string() {
+                    let mut year_title = date_selection.year().to_string();
+                    if show_heatmap { year_title += &format!(" - {} done", completed.iter().filter(|(d, _)| d.year() == date_selection.year()).map(|(_, n)| n).sum::<usize>()) }
+                    let year = Block::default()
+                        .title_alignment(Alignment::Center)
+                        .border_style(Style::default().fg(config.theme.focus))
+                        .borders(Borders::ALL);
+                    let year_rects = create_agenda_rects(year.inner(rects[0]), show_keys);
+                    let area = year_rects[0];
+                    // months are 21 wide and 8 high with a column between them
+                    let columns = (area.width / 22).clamp(1, 4);
+                    let rows = (12 + columns - 1) / columns;
+                    // scroll whole rows of months to keep the selected one on screen
+                    let shown = (area.height / 8).clamp(1, rows);
+                    let selected = (date_selection.month() as u16 - 1) / columns;
+                    let top = if selected < shown { 0 } else { selected + 1 - shown };
+                    let months = top*columns..((top + shown)*columns).min(12);
+                    if months.len() < 12 {
+                        let name = |m: u16| NaiveDate::from_ymd(2000, m as u32 + 1, 1).format("%b").to_string();
+                        year_title += &format!(" - {}-{}", name(months.start), name(months.end - 1));
+                    }
+                    let year = year.title(Spans::from(vec![ Span::styled(year_title, Style::default().fg(config.theme.title)), ]));
+                    for month in months {
+                        let rect = Rect { x: area.x + month % columns * 22, y: area.y + (month / columns - top) * 8, width: 21.min(area.width), height: 8.min(area.height) };
+                        let first = NaiveDate::from_ymd(date_selection.year(), month as u32 + 1, 1);
+                        let begin = week_begin(first, config.week_start);
+                        let mut lines = vec!(
+                            Spans::from(Span::styled(format!("{: ^21}", first.format("%B").to_string()), Style::default().fg(config.theme.title))),
+                            Spans::from((0..7).map(|i| format!("{:.2} ", (begin + chrono::Duration::days(i)).weekday().to_string())).collect::<String>()),
+                        );
+                        for week in 0..6 {
+                            lines.push(Spans::from((0..7).map(|i| {
+                                let date = begin + chrono::Duration::days(week*7 + i);
+                                if date.month() != first.month() { return Span::raw("   ") }
+                                let mut style = Style::default()
+                                    .fg(if holiday(&holidays, date).is_some() { config.theme.holiday } else { Color::White })
+                                    .bg(if date == today { config.theme.today } else if date == date_selection.date { config.theme.selection } else { Color::Reset });
+                                if date != today && date != date_selection.date { if let Some(color) = heat_color(&date) { style = style.bg(color).fg(foreground(color)) } }
+                                if notable_day(&visible_list, date, &holidays) { style = style.add_modifier(Modifier::UNDERLINED) }
+                                Span::styled(format!("{: >2} ", date.day()), style)
+                            }).collect::<Vec<Span>>()));
+                        }
+                        f.render_widget(Paragraph::new(lines), rect);
+                    }
+                    if show_keys {
+                        f.render_widget(
+                            Paragraph::new("H-L:yr J-K:mn j-k:wk h-l:dy a:heat c:done enter:open")
+                            .block(Block::default()
+                                .borders(Borders::TOP)
+                                .border_style(Style::default().fg(config.theme.focus))
+                        ), year_rects[1]);
+                    }
+                    f.render_widget(year, rects[0]);
+                } else if mode_selection.mode == "Report".to_string() {
+                    let (from, to) = report_selection.range(date_selection.date);
+                    let bars = report_selection.bars(&hours_report(&visible_list, from, to, &holidays));
+                    let report = Block::default()
+                        .title(Spans::from(vec![ Span::styled(format!("Hours by {:?}: {} to {}", report_selection.group, from.format("%b %d"), to.format("%b %d")),
+                            Style::default().fg(config.theme.title)), ]))
+                        .title_alignment(Alignment::Center)
+                        .border_style(Style::default().fg(config.theme.focus))
+                        .borders(Borders::ALL);
+                    let report_rects = create_agenda_rects(report.inner(rects[0]), show_keys);
+                    let label_width = bars.iter().map(|b| b.0.chars().count()).max().unwrap_or(0).min(20);
+                    let bar_width = (report_rects[0].width as usize).saturating_sub(label_width + 9);
+                    let most = bars.iter().map(|b| b.2).fold(0.0, f64::max);
+                    let text: Vec<Spans> = bars.iter().map(|(label, color, hours)| Spans::from(vec![
+                        Span::raw(format!("{: <width$} ", label.chars().take(label_width).collect::<String>(), width=label_width)),
+                        Span::styled("█".repeat((hours / most * bar_width as f64).round() as usize), Style::default().fg(if *color == Color::Reset { config.theme.title } else { *color })),
+                        Span::raw(format!(" {:.1}h", hours)),
+                    ])).collect();
+                    f.render_widget(Paragraph::new(if text.is_empty() { vec!(Spans::from("No events")) } else { text }), report_rects[0]);
+                    if show_keys {
+                        f.render_widget(
+                            Paragraph::new("h-l:period m:week/month g:group x:csv")
+                            .block(Block::default()
+                                .borders(Borders::TOP)
+                                .border_style(Style::default().fg(config.theme.focus))
+                        ), report_rects[1]);
+                    }
+                    f.render_widget(report, rects[0]);
+                } else if mode_selection.mode == "Agenda".to_string() {
+                    let agenda = Block::default()
+                        .title(Spans::from(vec![ Span::styled(format!("Agenda: {} days", agenda_selection.days), Style::default().fg(config.theme.title)), ]))
+                        .title_alignment(Alignment::Center)
+                        .border_style(Style::default().fg(config.theme.focus))
+                        .borders(Borders::ALL);
+                    let agenda_rects = create_agenda_rects(agenda.inner(rects[0]), show_keys);
+                    let list_items: Vec<ListItem> = agenda_selection.rows.iter().map(|(text, entry)|
+                        ListItem::new(text.clone()).style(if entry.is_none() { Style::default().fg(config.theme.title) } else { Style::default() })).collect();
+                    f.render_stateful_widget(List::new(list_items).highlight_style(Style::default().fg(config.theme.focus)), agenda_rects[0], &mut agenda_selection.state);
+                    if show_keys {
                         f.render_widget(
-                            Paragraph::new("~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~")
+                            Paragraph::new("j-k:entry +/-:days enter:open")
                             .block(Block::default()
                                 .borders(Borders::TOP)
-                                .border_style(Style::default().fg( if mode_selection.mode == "Contexts".to_string() {Color::Red} else {Color::White}))
-                        ), *rect);
-                    } else {
-                        let style = Style::default();
-                        let mut list_items: Vec<ListItem> = Vec::new();
-                        let mut hidden = 0;
-                        for (j, task) in context_selection.contexts[i].tasks.iter().enumerate() {
-                            let mut string = format!("{: <width$}{: >5}", task.description, task.urg, width=rect.width as usize-7);
-                            if context_selection.show_annos {
-                                if context_selection.all_annos {
-                                    for annotation in &task.annotation { string += "\n"; string += annotation }
-                                } else if !context_selection.all_annos && context_selection.index == i && Some(j) == context_selection.contexts[i].state.selected() {
-                                    for annotation in &task.annotation { string += "\n"; string += annotation }
-                                }                             }
-                            if task.status != "deleted" && task.status != "recurring" && !(!show_completed && task.status != "pending") {
-                                list_items.push(ListItem::new(string)
-                                .style(if task.status != "pending" {style.add_modifier(Modifier::DIM)} else {style}));
-                            } else { hidden += 1 }
+                                .border_style(Style::default().fg(config.theme.focus))
+                        ), agenda_rects[1]);
+                    }
+                    f.render_widget(agenda, rects[0]);
+                } else {
+                    let contexts = Block::default()
+                        .title(Spans::from(vec![ Span::styled("Contexts", Style::default().fg(config.theme.title)), ]))
+                        .title_alignment(Alignment::Center)
+                        .border_style(Style::default().fg( if mode_selection.mode == "Contexts".to_string() {config.theme.focus} else {Color::White}))
+                        .borders(Borders::ALL);
+                    // for rect in create_context_rects(contexts.inner(rects[0]), show_keys, context_list.len()+1) {
+                    let mut context_rects = create_context_rects(contexts.inner(rects[0]), show_keys, context_selection.len());
+                    // let extra_rect = context_rects[0];
+                    if context_selection.len() % 2 == 1 {
+                        context_rects[0] = context_rects[0].union(context_rects[1]);
+                        context_rects.remove(1);
+                    }
+                    // let rects = create_context_rects(contexts.inner(rects[0]), show_keys, context_list.len()+1);
+                    // println!("{}", context_selection.len());
+                    for (i, rect) in context_rects.iter().enumerate() {
+                        if show_keys && i == context_rects.len()-1 {
+                            f.render_widget(
+                                Paragraph::new("~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~")
+                                .block(Block::default()
+                                    .borders(Borders::TOP)
+                                    .border_style(Style::default().fg( if mode_selection.mode == "Contexts".to_string() {config.theme.focus} else {Color::White}))
+                            ), *rect);
+                        } else {
+                            let style = Style::default();
+                            let mut list_items: Vec<ListItem> = Vec::new();
+                            let mut hidden = 0;
+                            for (j, task) in context_selection.contexts[i].tasks.iter().enumerate() {
+                                let mut string = format!("{: <width$}{: >5}", task.description, task.urg, width=rect.width as usize-7);
+                                if context_selection.show_annos {
+                                    if context_selection.all_annos {
+                                        for annotation in &task.annotation { string += "\n"; string += annotation }
+                                    } else if !context_selection.all_annos && context_selection.index == i && Some(j) == context_selection.contexts[i].state.selected() {
+                                        for annotation in &task.annotation { string += "\n"; string += annotation }
+                                    }                             }
+                                if task.status != "deleted" && task.status != "recurring" && !(!show_completed && task.status != "pending") {
+                                    list_items.push(ListItem::new(string)
+                                    .style(if task.status != "pending" {style.add_modifier(Modifier::DIM)} else {style}));
+                                } else { hidden += 1 }
+                            }
+                            context_selection.contexts[i].hidden = hidden;
+                            let widget = List::new(list_items)
+                                .block(Block::default()
+                                    .title(Spans::from(vec![ Span::styled(context_selection.contexts[i].name.clone(), Style::default().fg(config.theme.title)), ]))
+                                    .title_alignment(Alignment::Center)
+                                    .border_style(Style::default().fg( if context_selection.index==i && context_selection.is_sel() {config.theme.focus} else {Color::White}))
+                                    .borders(Borders::ALL))
+                                .highlight_style(Style::default().fg(config.theme.title));
+                                f.render_stateful_widget(widget, *rect, &mut context_selection.contexts[i].state);
+                        }
+
+                    }
+                    f.render_widget(contexts, rects[0]);
+                }
+
+                // Event Details
+                if show_event {
+                    if let Some(event) = date_selection.event() {
+                        let occurrence = event.occurrences(sel_begin.date(), sel_begin.date(), &holidays).into_iter().find(|(begin, end)| *begin < sel_end && *end > sel_begin);
+                        let mut text = vec!(Spans::from(Span::styled(event.name.clone(), Style::default().fg(event.color).add_modifier(Modifier::BOLD))));
+                        if let Some((begin, end)) = occurrence {
+                            text.push(Spans::from(format!("{} - {}", begin.format("%a %b %d %H:%M"), end.format("%a %b %d %H:%M"))));
                         }
-                        context_selection.contexts[i].hidden = hidden;
-                        let widget = List::new(list_items)
+                        if event.location != "" { text.push(Spans::from(format!("at {}", event.location))) }
+                        if let Some(tz) = event.tz { text.push(Spans::from(format!("{} {}", event.time.format("%H:%M"), tz.name()))) }
+                        if !event.reminders.is_empty() {
+                            text.push(Spans::from(format!("reminders: {} minutes before", event.reminders.iter().map(|r| r.to_string()).collect::<Vec<String>>().join(", "))))
+                        }
+                        if !event.days.is_empty() {
+                            text.push(Spans::from(format!("{} on {}", event.repeat_cycle.name(), event.days.iter().map(|d| d.name()).collect::<Vec<String>>().join(", "))))
+                        }
+                        text.push(Spans::from(format!("tasks: {}", event.task_modifier)));
+                        if event.description != "" { text.push(Spans::from("")); text.push(Spans::from(event.description.clone())) }
+                        let rect = create_popup_rect(f.size(), 60, 40);
+                        f.render_widget(Clear, rect);
+                        f.render_widget(Paragraph::new(text).wrap(Wrap { trim: true })
                             .block(Block::default()
-                                .title(Spans::from(vec![ Span::styled(context_selection.contexts[i].name.clone(), Style::default().fg(Color::Blue)), ]))
+                                .title(Spans::from(vec![ Span::styled(format!("{}:{}", event.file, event.line), Style::default().fg(config.theme.title)), ]))
                                 .title_alignment(Alignment::Center)
-                                .border_style(Style::default().fg( if context_selection.index==i && context_selection.is_sel() {Color::Red} else {Color::White}))
-                                .borders(Borders::ALL))
-                            .highlight_style(Style::default().fg(Color::Blue));
-                            f.render_stateful_widget(widget, *rect, &mut context_selection.contexts[i].state);
+                                .border_style(Style::default().fg(config.theme.focus))
+                                .borders(Borders::ALL)), rect);
+                    }
+                }
+
+                // Toast
+                if let Some((text, _)) = &toast {
+                    let width = (text.len() as u16 + 4).min(f.size().width);
+                    let rect = Rect { x: f.size().width - width, y: 0, width, height: 3.min(f.size().height) };
+                    f.render_widget(Clear, rect);
+                    f.render_widget(Paragraph::new(text.clone()).alignment(Alignment::Center)
+                        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Yellow))), rect);
+                }
+
+                // Parse Errors
+                if show_errors {
+                    let rect = create_popup_rect(f.size(), 80, 60);
+                    let text = parse_errors.iter().map(|e| e.to_string()).collect::<Vec<String>>().join("\n") + "\n\nThese lines were skipped, e:close";
+                    f.render_widget(Clear, rect);
+                    f.render_widget(Paragraph::new(text).wrap(Wrap { trim: false })
+                        .block(Block::default()
+                            .title(Spans::from(vec![ Span::styled("Errors", Style::default().fg(Color::Red)), ]))
+                            .title_alignment(Alignment::Center)
+                            .border_style(Style::default().fg(config.theme.focus))
+                            .borders(Borders::ALL)), rect);
+                }
+
+                // Lint
+                if show_lint {
+                    let rect = create_popup_rect(f.size(), 80, 60);
+                    let text = if lints.is_empty() { "No problems found".to_string() }
+                        else { lints.iter().map(|l| l.to_string()).collect::<Vec<String>>().join("\n") };
+                    f.render_widget(Clear, rect);
+                    f.render_widget(Paragraph::new(text)
+                        .block(Block::default()
+                            .title(Spans::from(vec![ Span::styled("Lint", Style::default().fg(config.theme.title)), ]))
+                            .title_alignment(Alignment::Center)
+                            .border_style(Style::default().fg(config.theme.focus))
+                            .borders(Borders::ALL)), rect);
+                }
+
+                // Planned vs Actual
+                if show_report {
+                    let rect = create_popup_rect(f.size(), 60, 70);
+                    let week_begin = week_begin(date_selection.date, config.week_start);
+                    let mut text = Vec::new();
+                    for (title, from, to) in [(format!("{}", date_selection.date.format("%a %Y-%m-%d")), date_selection.date, date_selection.date),
+                                              (format!("Week of {}", week_begin.format("%Y-%m-%d")), week_begin, week_begin + chrono::Duration::days(6))] {
+                        text.push(Spans::from(Span::styled(format!("{: <24}{: >8}{: >8}", title, "planned", "actual"), Style::default().fg(config.theme.title))));
+                        for (name, planned, actual) in planned_actual(&visible_list, &time_log, from, to, &holidays) {
+                            text.push(Spans::from(format!("{: <24}{: >8.1}{: >8.1}", name, planned, actual)));
+                        }
+                        text.push(Spans::from(""));
                     }
+                    f.render_widget(Clear, rect);
+                    f.render_widget(Paragraph::new(text)
+                        .block(Block::default()
+                            .title(Spans::from(vec![ Span::styled("Planned vs Actual", Style::default().fg(config.theme.title)), ]))
+                            .title_alignment(Alignment::Center)
+                            .border_style(Style::default().fg(config.theme.focus))
+                            .borders(Borders::ALL)), rect);
+                }
+
+                // Completed Tasks
+                if show_completions {
+                    let rect = create_popup_rect(f.size(), 60, 50);
+                    let mut done: Vec<&Task> = task_list.iter().filter(|t| t.status == "completed" && t.end.map(|e| e.date()) == Some(date_selection.date)).collect();
+                    done.sort_by_key(|t| t.end);
+                    let list_items: Vec<ListItem> = if done.is_empty() { vec!(ListItem::new("Nothing completed")) }
+                        else { done.iter().map(|t| ListItem::new(Spans::from(vec![
+                            Span::styled(t.end.unwrap().format("%H:%M  ").to_string(), Style::default().fg(Color::DarkGray)),
+                            Span::styled(if t.project != "" { format!("{}  ", t.project) } else { "".to_string() }, Style::default().fg(config.theme.title)),
+                            Span::raw(t.description.clone()),
+                        ]))).collect() };
+                    f.render_widget(Clear, rect);
+                    f.render_widget(List::new(list_items)
+                        .block(Block::default()
+                            .title(Spans::from(vec![ Span::styled(format!("Completed {}", date_selection.date.format("%a %Y-%m-%d")), Style::default().fg(config.theme.title)), ]))
+                            .title_alignment(Alignment::Center)
+                            .border_style(Style::default().fg(config.theme.focus))
+                            .borders(Borders::ALL)), rect);
+                }
+
+                // Free Slots
+                if let Some(slots) = &mut slot_selection {
+                    let rect = create_popup_rect(f.size(), 50, 50);
+                    let list_items: Vec<ListItem> = if slots.slots.is_empty() { vec!(ListItem::new("No free slots in the next four weeks")) }
+                        else { slots.slots.iter().map(|(begin, end)| ListItem::new(format!("{} {}-{}", begin.format("%a %b %d"), begin.format("%H:%M"), end.format("%H:%M")))).collect() };
+                    f.render_widget(Clear, rect);
+                    f.render_stateful_widget(List::new(list_items)
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                        .block(Block::default()
+                            .title(Spans::from(vec![ Span::styled(format!("Free {}: enter:jump a:add esc:close", slots.query), Style::default().fg(config.theme.title)), ]))
+                            .title_alignment(Alignment::Center)
+                            .border_style(Style::default().fg(config.theme.focus))
+                            .borders(Borders::ALL)), rect, &mut slots.state);
+                }
+
+                // Prompt
+                if let Some((purpose, input)) = &prompt {
+                    let rect = Rect { height: 3.min(f.size().height), ..create_popup_rect(f.size(), 60, 10) };
+                    let label = match purpose { Prompt::FreeSlot => "Free slot (2h, weekdays 9-18)", Prompt::EventName(_, _) => "Event name", Prompt::GoToWeek => "Go to ISO week (N or N YYYY)" };
+                    f.render_widget(Clear, rect);
+                    f.render_widget(Paragraph::new(format!("{}█", input))
+                        .block(Block::default()
+                            .title(Spans::from(vec![ Span::styled(label, Style::default().fg(config.theme.title)), ]))
+                            .border_style(Style::default().fg(config.theme.focus))
+                            .borders(Borders::ALL)), rect);
+                }
 
+                // Calendars
+                if calendar_selection.show {
+                    let rect = create_popup_rect(f.size(), 40, 40);
+                    let list_items: Vec<ListItem> = calendar_selection.calendars.iter().map(|c|
+                        ListItem::new(Spans::from(vec![
+                            Span::raw(if c.visible { "[x] " } else { "[ ] " }),
+                            Span::styled(c.name.clone(), Style::default().fg(fit_color(c.color, config.color_depth))),
+                        ]))).collect();
+                    f.render_widget(Clear, rect);
+                    f.render_stateful_widget(List::new(list_items)
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                        .block(Block::default()
+                            .title(Spans::from(vec![ Span::styled("Calendars", Style::default().fg(config.theme.title)), ]))
+                            .title_alignment(Alignment::Center)
+                            .border_style(Style::default().fg(config.theme.focus))
+                            .borders(Borders::ALL)), rect, &mut calendar_selection.state);
                 }
-                f.render_widget(contexts, rects[0]);
             }
         })?;
 
@@ -690,6 +2300,86 @@ fn main() -> Result<(), Error> {
         // Key Handling
         let x = it.next();
         let event = x.unwrap_or(Ok(Key::Null))?;
+        if let Some((purpose, input)) = &mut prompt {
+            match event {
+                Key::Char('\n') => {
+                    let now = Local::now().naive_local();
+                    match purpose.clone() {
+                        Prompt::FreeSlot => match SlotQuery::parse(input) {
+                            Ok(query) => {
+                                let mut slots = SlotSelection { query: input.clone(), hours: query.hours, slots: free_slots(&sched_list, &query, now, 10, &holidays), state: ListState::default() };
+                                if !slots.slots.is_empty() { slots.next() }
+                                slot_selection = Some(slots);
+                            }
+                            Err(message) => toast = Some((message, now)),
+                        }
+                        Prompt::GoToWeek => {
+                            let fields: Vec<&str> = input.split_whitespace().collect();
+                            let week = fields.get(0).and_then(|w| w.parse::<u32>().ok());
+                            let year = fields.get(1).map_or(Some(date_selection.date.iso_week().year()), |y| y.parse::<i32>().ok());
+                            match (week, year) {
+                                (Some(week), Some(year)) => match NaiveDate::from_isoywd_opt(year, week, chrono::Weekday::Mon) {
+                                    Some(date) => date_selection.set_date(date),
+                                    None => toast = Some((format!("{} has no week {}", year, week), now)),
+                                }
+                                _ => toast = Some((format!("'{}' is not a week number and optional year", input), now)),
+                            }
+                        }
+                        Prompt::EventName(begin, hours) => if input.trim() != "" {
+                            let event = Event::new(input.trim().to_string(), begin.time(), begin.date(), hours, Cycle::Never, 0,
+                                fit_color(parse_color(&config.block_color).unwrap_or(Color::Cyan), config.color_depth), "none".to_string());
+                            match append_block(schedule_path(), event, &config.block_color) {
+                                Ok(event) => { sched_list.push(event); visible_list = calendar_selection.visible(&sched_list) }
+                                Err(e) => toast = Some((format!("Could not write {}: {}", schedule_path(), e), now)),
+                            }
+                            date_selection.set_date(begin.date()); date_selection.set_time(begin.time()); mode_selection.schedule();
+                        }
+                    }
+                    prompt = None;
+                }
+                Key::Char(c) => input.push(c),
+                Key::Backspace => { input.pop(); }
+                Key::Esc => prompt = None,
+                Key::Null => thread::sleep(Duration::from_millis(10)),
+                _ => (),
+            }
+            continue
+        }
+        if let Some(slots) = &mut slot_selection {
+            match event {
+                Key::Char('j') => if !slots.slots.is_empty() { slots.next() },
+                Key::Char('k') => if !slots.slots.is_empty() { slots.prev() },
+                Key::Char('\n') => if let Some((begin, _)) = slots.selected() {
+                    date_selection.set_date(begin.date()); date_selection.set_time(begin.time()); mode_selection.schedule();
+                    slot_selection = None;
+                }
+                Key::Char('a') => if let Some((begin, _)) = slots.selected() {
+                    prompt = Some((Prompt::EventName(begin, slots.hours), "".to_string()));
+                    slot_selection = None;
+                }
+                Key::Esc => slot_selection = None,
+                Key::Char('q') => break,
+                Key::Null => thread::sleep(Duration::from_millis(10)),
+                _ => (),
+            }
+            continue
+        }
+        if calendar_selection.show {
+            match event {
+                Key::Char('j') => calendar_selection.next(),
+                Key::Char('k') => calendar_selection.prev(),
+                Key::Char(' ') => {
+                    calendar_selection.toggle();
+                    visible_list = calendar_selection.visible(&sched_list);
+                    if mode_selection.mode == "Agenda".to_string() { agenda_selection.populate(today, &visible_list, &task_list, &holidays) }
+                }
+                Key::Char('v') | Key::Esc => calendar_selection.show = false,
+                Key::Char('q') => break,
+                Key::Null => thread::sleep(Duration::from_millis(10)),
+                _ => (),
+            }
+            continue
+        }
         match mode_selection.mode.as_str() {
             "Contexts" => match event {
                 Key::BackTab => {context_selection.deselect(); for context in &mut context_selection.contexts { context.deselect() };}
@@ -700,7 +2390,31 @@ fn main() -> Result<(), Error> {
                 Key::Char('K') => {context_selection.prev(); context_selection.select(); for context in &mut context_selection.contexts { context.deselect() }},
                 Key::Char(' ') => {context_selection.toggle_show_annos()},
                 Key::Char('A') => {context_selection.toggle_all_annos()},
-                Key::Char('c') => if let Some(task) = context_selection.task() { task.toggle_complete() },
+                Key::Char('c') => if let Some(task) = context_selection.task() {
+                    task.toggle_complete();
+                    let (uuid, status, end) = (task.uuid.clone(), task.status.clone(), task.end);
+                    let now = Local::now().naive_local();
+                    if let Some((event, begin)) = block_in(&sched_list, now, now + chrono::Duration::minutes(1), &uuid, &holidays).filter(|_| status == "completed") {
+                        let entry = LogEntry { begin, mark: "completed".to_string(), event: event.name, task: uuid.clone() };
+                        if let Err(e) = append_log("timelog", &entry) { toast = Some((format!("Could not write timelog: {}", e), now)) }
+                        time_log.push(entry);
+                    }
+                    for task in task_list.iter_mut().chain(context_selection.contexts.iter_mut().flat_map(|c| c.tasks.iter_mut())).filter(|t| t.uuid == uuid) {
+                        task.status = status.clone();
+                        task.end = end
+                    }
+                },
+                Key::Char('s') => if let Some(task) = context_selection.task() {
+                    let now = Local::now().naive_local();
+                    if task.start() {
+                        if let Some((event, begin)) = block_in(&sched_list, now, now + chrono::Duration::minutes(1), &task.uuid, &holidays) {
+                            let entry = LogEntry { begin, mark: "started".to_string(), event: event.name, task: task.uuid.clone() };
+                            if let Err(e) = append_log("timelog", &entry) { toast = Some((format!("Could not write timelog: {}", e), now)) }
+                            time_log.push(entry);
+                        }
+                    }
+                },
+                Key::Char('b') => if let Some(task) = context_selection.task() { block_task = Some(task.clone()); mode_selection.schedule() },
                 Key::Char('.') => show_completed = !show_completed,
                 _ => (),
             }
@@ -714,8 +2428,10 @@ fn main() -> Result<(), Error> {
                     }
                     Key::Null => match event {
                         Key::BackTab => {context_selection.select()}
-                        Key::Char('j') => {date_selection.next_hour()},
-                        Key::Char('k') => {date_selection.prev_hour()},
+                        Key::Char('j') => date_selection.next_slot(row_minutes),
+                        Key::Char('k') => date_selection.prev_slot(row_minutes),
+                        Key::Char('+') => if let Some(i) = ROW_MINUTES.iter().position(|m| *m == row_minutes).filter(|i| *i > 0) { row_minutes = ROW_MINUTES[i-1] },
+                        Key::Char('-') => if let Some(i) = ROW_MINUTES.iter().position(|m| *m == row_minutes).filter(|i| *i < ROW_MINUTES.len()-1) { row_minutes = ROW_MINUTES[i+1] },
                         Key::Char('l') => date_selection.next_day(),
                         Key::Char('h') => date_selection.prev_day(),
                         Key::Char('J') => {date_selection.next_week()},
@@ -723,16 +2439,99 @@ fn main() -> Result<(), Error> {
                         Key::Char('L') => date_selection.next_month(),
                         Key::Char('H') => date_selection.prev_month(),
                         Key::Char('g') => mode_selection.leader = Key::Char('g'),
+                        Key::Char('w') => week_view = !week_view,
+                        Key::Char('\n') => match block_task.take() {
+                            Some(task) => {
+                                let mut event = Event::new(task.description.clone(), NaiveTime::from_hms(date_selection.hour(), 0, 0), date_selection.date, 1, Cycle::Never, 0,
+                                    fit_color(parse_color(&config.block_color).unwrap_or(Color::Cyan), config.color_depth), "none".to_string());
+                                event.task = task.uuid.clone();
+                                match append_block(schedule_path(), event, &config.block_color) {
+                                    Ok(event) => { sched_list.push(event); visible_list = calendar_selection.visible(&sched_list) }
+                                    Err(e) => toast = Some((format!("Could not write {}: {}", schedule_path(), e), Local::now().naive_local())),
+                                }
+                            }
+                            None => show_event = !show_event,
+                        }
+                        Key::Esc => { show_event = false; block_task = None },
+                        Key::Char(c) if c == 'd' || c == 'x' => {
+                            let sel_begin = date_selection.slot(row_minutes);
+                            let now = Local::now().naive_local();
+                            // the event shown at the selected row
+                            let shown: Vec<Event> = date_selection.event().into_iter().collect();
+                            if let Some((event, begin)) = block_in(&shown, sel_begin, sel_begin + chrono::Duration::minutes(row_minutes), "", &holidays) {
+                                let entry = LogEntry { begin, mark: (if c == 'd' { "done" } else { "skipped" }).to_string(), event: event.name, task: "".to_string() };
+                                toast = Some((match append_log("timelog", &entry) {
+                                    Ok(_) => format!("{} {}", entry.event, entry.mark),
+                                    Err(e) => format!("Could not write timelog: {}", e),
+                                }, now));
+                                time_log.push(entry);
+                            }
+                        }
+                        Key::Char('f') => prompt = Some((Prompt::FreeSlot, "".to_string())),
+                        Key::Char('z') => show_secondary = !show_secondary && config.secondary_timezone.is_some(),
                         _ => (),
                     }
                     _ => (),
                 }
             }
+            "Agenda" => match event {
+                Key::Char('j') => agenda_selection.next(),
+                Key::Char('k') => agenda_selection.prev(),
+                Key::Char('+') => agenda_selection.days += 7,
+                Key::Char('-') => if agenda_selection.days > 7 { agenda_selection.days -= 7 },
+                Key::Char('\n') => match agenda_selection.selected() {
+                    Some(AgendaEntry::Event(begin)) => {
+                        date_selection.set_date(begin.date()); date_selection.set_time(begin.time()); mode_selection.schedule()
+                    }
+                    Some(AgendaEntry::Task(task, datetime)) => {
+                        date_selection.set_date(datetime.date());
+                        for context in &mut context_selection.contexts { context.deselect() }
+                        if let Some(i) = context_selection.contexts.iter_mut().position(|c| c.select_task(&task.uuid)) {
+                            context_selection.index = i; context_selection.select();
+                        }
+                        mode_selection.contexts()
+                    }
+                    None => (),
+                }
+                _ => (),
+            }
+            "Year" => match event {
+                Key::Char('L') => date_selection.next_year(),
+                Key::Char('H') => date_selection.prev_year(),
+                Key::Char('J') => date_selection.next_month(),
+                Key::Char('K') => date_selection.prev_month(),
+                Key::Char('j') => date_selection.next_week(),
+                Key::Char('k') => date_selection.prev_week(),
+                Key::Char('l') => date_selection.next_day(),
+                Key::Char('h') => date_selection.prev_day(),
+                Key::Char('t') => date_selection.set_date(today),
+                Key::Char('a') => show_heatmap = !show_heatmap,
+                Key::Char('c') => show_completions = !show_completions,
+                Key::Esc => show_completions = false,
+                Key::Char('\n') => mode_selection.schedule(),
+                _ => (),
+            }
+            "Report" => match event {
+                Key::Char('h') => if report_selection.monthly { date_selection.prev_month() } else { date_selection.prev_week() },
+                Key::Char('l') => if report_selection.monthly { date_selection.next_month() } else { date_selection.next_week() },
+                Key::Char('m') => report_selection.monthly = !report_selection.monthly,
+                Key::Char('g') => report_selection.next_group(),
+                Key::Char('x') => {
+                    let (from, to) = report_selection.range(date_selection.date);
+                    let path = format!("report-{}.csv", from.format("%Y-%m-%d"));
+                    toast = Some((match std::fs::write(&path, report_csv(&hours_report(&visible_list, from, to, &holidays))) {
+                        Ok(_) => format!("Wrote {}", path),
+                        Err(e) => format!("Could not write {}: {}", path, e),
+                    }, Local::now().naive_local()));
+                }
+                _ => (),
+            }
             "Calendar" => {
                 match mode_selection.leader {
                     Key::Char('g') => match event {
                         Key::Char('h') => {date_selection.set_date(today); mode_selection.reset_leader()},
                         Key::Char('0') => {date_selection.set_date(NaiveDate::from_ymd(date_selection.year(),1,1)); mode_selection.reset_leader()},
+                        Key::Char('w') => {prompt = Some((Prompt::GoToWeek, "".to_string())); mode_selection.reset_leader()},
                         Key::Null => (),
                         _ => mode_selection.reset_leader(),
                     }
@@ -747,6 +2546,17 @@ fn main() -> Result<(), Error> {
                         Key::Char('k') => date_selection.prev_week(),
                         Key::Char('l') => date_selection.next_day(),
                         Key::Char('h') => date_selection.prev_day(),
+                        Key::Char('n') => week_numbers = !week_numbers,
+                        Key::Char('m') => {
+                            three_months = !three_months;
+                            let (width, _) = termion::terminal_size().unwrap_or((0, 0));
+                            if three_months && width < calendar_width(3, week_numbers) + 60 {
+                                toast = Some((format!("Three months need a terminal {} columns wide", calendar_width(3, week_numbers) + 60), Local::now().naive_local()))
+                            }
+                        }
+                        Key::Char('a') => show_heatmap = !show_heatmap,
+                        Key::Char('c') => show_completions = !show_completions,
+                        Key::Esc => show_completions = false,
                         // Key::Char('a') => task_selection.items.push(date_selection.date.to_string()),
                         // Key::Char('p') => task_selection.items.push(date_selection.day().to_string()),
                         Key::Char('g') => mode_selection.leader = Key::Char('g'),
@@ -762,12 +2572,36 @@ fn main() -> Result<(), Error> {
             Key::Char('\t') => mode_selection.next(),
             Key::BackTab => mode_selection.prev(),
             Key::Char('?') => show_keys = !show_keys,
+            Key::Char('e') => show_errors = !show_errors && !parse_errors.is_empty(),
+            Key::Char('v') => if mode_selection.mode != "Contexts".to_string() {
+                calendar_selection.show = true;
+                if calendar_selection.state.selected().is_none() { calendar_selection.next() }
+            }
+            Key::Char('r') => show_report = !show_report,
+            // start a pomodoro on the selected task, or stop the running one
+            Key::Char('p') => match pomodoro.take() {
+                Some(mut timer) => if config.pomodoro_task && timer.work { if let Some(task) = timer.task.as_mut() { task.stop(); } },
+                None => {
+                    let now = Local::now().naive_local();
+                    let mut task = context_selection.task().cloned();
+                    if config.pomodoro_task { if let Some(task) = task.as_mut() { task.start(); } }
+                    pomodoro = Some(Pomodoro { task, work: true, began: now, ends: now + chrono::Duration::minutes(config.pomodoro_work) });
+                }
+            }
+            Key::Char('!') => {
+                show_lint = !show_lint;
+                if show_lint { lints = lint_schedule(&sched_entries, date_selection.date, date_selection.date + chrono::Duration::days(27), &holidays) }
+            }
             Key::Char('1') => mode_selection.contexts(),
             Key::Char('2') => mode_selection.schedule(),
             Key::Char('3') => mode_selection.calendar(),
+            Key::Char('4') => mode_selection.agenda(),
+            Key::Char('5') => mode_selection.report(),
+            Key::Char('6') => mode_selection.year(),
             Key::Null => {thread::sleep(Duration::from_millis(10)); continue},
             _ => (),
         }
+        if mode_selection.mode == "Agenda".to_string() { agenda_selection.populate(today, &visible_list, &task_list, &holidays) }
         if show_title {
             match event {
                 Key::Null => (),
@@ -808,52 +2642,114 @@ fn create_context_rects(size: Rect, show_keys: bool, count: usize) -> Vec<Rect>
     rects
 }
 
-fn create_cal_rects(size: Rect, show_keys: bool) -> Vec<Rect> {
+// the calendar pane's width with months side by side, two columns apart
+fn calendar_width(months: u16, week_numbers: bool) -> u16 { months*(28+3*week_numbers as u16) + 2*(months-1) + 2 }
+
+// the day cells of each month in turn, then the key hints, and a week number cell per row when week_numbers is set
+fn create_cal_rects(size: Rect, show_keys: bool, week_numbers: bool, months: usize) -> (Vec<Rect>, Vec<Rect>) {
     let rows: usize = 7 + show_keys as usize;
     let init_rects = Layout::default()
         .direction(Direction::Vertical)
         .constraints( vec![ Constraint::Length(1); rows ].as_ref())
         .split(size);
-    let mut rects: Vec<Rect> = Vec::new();
+    let mut rects: Vec<Vec<Rect>> = vec![ Vec::new(); months ];
+    let mut week_rects: Vec<Vec<Rect>> = vec![ Vec::new(); months ];
+    let mut keys = None;
+    let mut constraints = Vec::new();
+    for month in 0..months {
+        if month > 0 { constraints.push(Constraint::Length(2)) }
+        if week_numbers { constraints.push(Constraint::Length(3)) }
+        constraints.extend(vec![ Constraint::Length(4); 7 ]);
+    }
     for (i, rect) in init_rects.iter().enumerate() {
-        if show_keys && i == init_rects.len()-1 { rects.push(*rect) }
+        if show_keys && i == init_rects.len()-1 { keys = Some(*rect) }
         else{
-            rects.extend(Layout::default()
+            let row = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints( [ Constraint::Length(4); 7 ].as_ref())
-                .split(*rect));
+                .constraints(constraints.clone())
+                .split(*rect);
+            // skip the gap before each month after the first
+            let width = 7 + week_numbers as usize;
+            for month in 0..months {
+                let mut cells = row[month*(width+1)..month*(width+1)+width].to_vec();
+                if week_numbers { week_rects[month].push(cells.remove(0)) }
+                rects[month].extend(cells);
+            }
         }
     }
-    rects
+    let mut rects = rects.concat();
+    rects.extend(keys);
+    (rects, week_rects.concat())
 }
 
-fn create_sched_rects(size: Rect, show_keys: bool) -> (Vec<Rect>, Vec<Rect>) {
+fn create_sched_rects(size: Rect, show_keys: bool, secondary: bool, row_height: u16) -> (Vec<Rect>, Vec<Rect>, Vec<Rect>) {
     // let rows: usize = ()(size.height/2) + show_keys as usize;
-    let constraints = vec![ Constraint::Length(2); (size.height/2).into() ];
+    let mut constraints = vec![ Constraint::Length(row_height); (size.height/row_height).into() ];
+    // the key hints need two lines
+    if show_keys && row_height == 1 { constraints.pop(); constraints.pop(); constraints.push(Constraint::Length(2)) }
     let init_rects = Layout::default()
         .direction(Direction::Vertical)
         .constraints( constraints.as_ref())
         .split(size);
     let mut left_rects: Vec<Rect> = Vec::new();
+    let mut zone_rects: Vec<Rect> = Vec::new();
     let mut right_rects: Vec<Rect> = Vec::new();
     for (i, rect) in init_rects.iter().enumerate() {
         if show_keys && i == init_rects.len()-1 { left_rects.push(*rect) }
         else{
             let rects = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints( [ Constraint::Length(3), Constraint::Min(3) ].as_ref())
+                .constraints( if secondary { vec!(Constraint::Length(3), Constraint::Length(6), Constraint::Min(3)) }
+                              else { vec!(Constraint::Length(3), Constraint::Min(3)) })
                 .split(*rect);
             left_rects.push(rects[0]);
-            right_rects.push(rects[1]);
+            if secondary { zone_rects.push(rects[1]) }
+            right_rects.push(rects[rects.len()-1]);
         }
     }
-    (left_rects, right_rects)
+    (left_rects, zone_rects, right_rects)
 }
 
-fn create_rects(size: Rect, show_keys: bool) -> Vec<Rect> {
+fn create_week_rects(size: Rect, show_keys: bool) -> (Vec<Rect>, Vec<Vec<Rect>>) {
+    let mut init_rects = vec!(size);
+    if show_keys {
+        init_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints( vec!(Constraint::Min(2), Constraint::Length(2)) )
+            .split(size);
+    }
+    let row_rects = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints( vec![ Constraint::Length(1); init_rects[0].height.into() ] )
+        .split(init_rects[0]);
+    let mut left_rects: Vec<Rect> = Vec::new();
+    let mut day_rects: Vec<Vec<Rect>> = Vec::new();
+    for rect in row_rects.iter() {
+        let mut constraints = vec!(Constraint::Length(3));
+        constraints.extend(vec![ Constraint::Ratio(1, 7); 7 ]);
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(*rect);
+        left_rects.push(rects[0]);
+        day_rects.push(rects[1..].to_vec());
+    }
+    if show_keys { left_rects.push(init_rects[1]) }
+    (left_rects, day_rects)
+}
+
+fn create_agenda_rects(size: Rect, show_keys: bool) -> Vec<Rect> {
+    if !show_keys { return vec!(size) }
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints( vec!(Constraint::Min(1), Constraint::Length(2)) )
+        .split(size)
+}
+
+fn create_rects(size: Rect, show_keys: bool, week_numbers: bool, months: u16) -> Vec<Rect> {
     let mut rects = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints( [ Constraint::Length(30), Constraint::Min(30), ].as_ref())
+        .constraints( [ Constraint::Length(calendar_width(months, week_numbers)), Constraint::Min(30), ].as_ref())
         .split(size);
 
     let subrects = Layout::default()
@@ -864,17 +2760,21 @@ fn create_rects(size: Rect, show_keys: bool) -> Vec<Rect> {
     rects
 }
 
-fn create_dates(sel_date: &DateSelection) -> Vec<NaiveDate> {
-    let mut prev_month = DateSelection::date(sel_date.date); prev_month.prev_month();
-    let curr_month = DateSelection::date(sel_date.date);
-    let mut next_month = DateSelection::date(sel_date.date); next_month.next_month();
+fn create_popup_rect(size: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let rects = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints( [ Constraint::Percentage((100-percent_y)/2), Constraint::Percentage(percent_y), Constraint::Percentage((100-percent_y)/2) ].as_ref())
+        .split(size);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints( [ Constraint::Percentage((100-percent_x)/2), Constraint::Percentage(percent_x), Constraint::Percentage((100-percent_x)/2) ].as_ref())
+        .split(rects[1])[1]
+}
 
-    let mut dates: Vec<NaiveDate> = Vec::new();
-    for date in prev_month.month_length()-curr_month.first().weekday().num_days_from_sunday()-7..prev_month.month_length() { dates.push(NaiveDate::from_ymd(prev_month.year(), prev_month.month(), (date+1).into()))};
-    for date in 0..curr_month.month_length() { dates.push(NaiveDate::from_ymd(curr_month.year(), curr_month.month(), (date+1).into())) };
-    let offset = if dates.len() < 42 { 14 } else { 7 };
-    for date in 0..offset-next_month.first().weekday().num_days_from_sunday() { dates.push(NaiveDate::from_ymd(next_month.year(), next_month.month(), (date+1).into()))};
-    return dates
+// a week of header days, then six weeks starting with the one holding the 1st
+fn create_dates(sel_date: &DateSelection, week_start: chrono::Weekday) -> Vec<NaiveDate> {
+    let begin = week_begin(sel_date.first(), week_start) - chrono::Duration::days(7);
+    (0..49).map(|i| begin + chrono::Duration::days(i)).collect()
 }
 
 fn create_title(size: Rect) -> (Paragraph<'static>, Paragraph<'static>) {
@@ -923,3 +2823,175 @@ fn create_title(size: Rect) -> (Paragraph<'static>, Paragraph<'static>) {
 
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(line: &str) -> Event { Event::parse(line, 1).unwrap() }
+    fn at(y: i32, m: u32, d: u32, h: u32) -> NaiveDateTime { NaiveDate::from_ymd(y, m, d).and_hms(h, 0, 0) }
+
+    #[test]
+    fn migrated_schedule_reads_back_the_same() {
+        let legacy = "# comments are kept
+Black     1 1 1     2    8   D   0         Sleep    sleep
+Red       1 1 1     10   1   D   0         \"Wake Up\" wake  remind=10,60 desc=\"Coffee first\" loc=\"Kitchen table\"
+#2e8b57   1 1 1     9    1   W   00111110  Standup  none     tz=America/New_York
+Green     1 1 1     9    1   M   0         Invoice  none     on=1st,15,lastbday holidays=skip
+Cyan      2026 10 20 12  2   N   0         Block    none     task=5b0f
+[gym Magenta]
+-         1 1 1     21   2   W   01010010  Lift     lift:gym
+";
+        let dir = std::env::temp_dir();
+        let (legacy_path, migrated_path) = (dir.join(format!("taskcalrs-{}", std::process::id())), dir.join(format!("taskcalrs-{}.toml", std::process::id())));
+        let (text, lints) = migrate_schedule("fixture", &legacy.lines().map(|l| l.to_string()).collect::<Vec<String>>());
+        assert!(lints.is_empty(), "{:?}", lints);
+        std::fs::write(&legacy_path, legacy).unwrap();
+        std::fs::write(&migrated_path, text).unwrap();
+        let default = Calendar { name: "schedule".to_string(), color: Color::Reset, file: "".to_string(), visible: true };
+        let (mut legacy_calendars, mut migrated_calendars) = (vec!(default.clone()), vec!(default));
+        let legacy = read_schedule(legacy_path.to_str().unwrap(), &mut legacy_calendars).unwrap();
+        let migrated = read_schedule(migrated_path.to_str().unwrap(), &mut migrated_calendars).unwrap();
+        std::fs::remove_file(&legacy_path).unwrap();
+        std::fs::remove_file(&migrated_path).unwrap();
+        // only where each event was read from differs
+        let strip = |entries: Vec<Result<Event, ParseError>>| entries.into_iter().map(|e| Event { line: 0, file: "".to_string(), ..e.unwrap() }).collect::<Vec<Event>>();
+        let (legacy, migrated) = (strip(legacy), strip(migrated));
+        assert_eq!(legacy.len(), 6);
+        assert_eq!(legacy, migrated);
+        let strip = |calendars: Vec<Calendar>| calendars.into_iter().map(|c| Calendar { file: "".to_string(), ..c }).collect::<Vec<Calendar>>();
+        assert_eq!(strip(legacy_calendars), strip(migrated_calendars));
+    }
+
+    #[test]
+    fn full_occurrence_masks() {
+        assert_eq!(event("Red 1 1 1 9 1 W 11111110 Every none").repeat_occurences, 0b11111110);
+        assert_eq!(Event::parse("Red 1 1 1 9 1 W 111111110 Every none", 1).err().map(|e| e.0), Some(17));
+        assert_eq!(Event::parse("Red 1 1 1 9 1 W +1 Every none", 1).err().map(|e| e.0), Some(17));
+    }
+
+    #[test]
+    fn full_table_masks() {
+        let table = |mask: &str| format!("name = \"Every\"\nstart = \"09:00\"\nrepeat = \"weekly\"\nmask = \"{}\"", mask).parse::<toml::Value>().unwrap();
+        assert_eq!(Event::from_table(&table("11111110"), 1).unwrap().repeat_occurences, 0b11111110);
+        assert!(Event::from_table(&table("111111110"), 1).is_err());
+    }
+
+    #[test]
+    fn occurrence_crossing_midnight() {
+        // Mondays 23:00 for three hours
+        let late = event("Blue 1 1 1 23 3 W 00100000 Late none");
+        let monday = NaiveDate::from_ymd(2026, 10, 19);
+        assert_eq!(late.occurrences(monday, monday, &[]), vec!((at(2026, 10, 19, 23), at(2026, 10, 20, 2))));
+        assert_eq!(late.occurrences(monday.succ(), monday.succ(), &[]), vec!((at(2026, 10, 19, 23), at(2026, 10, 20, 2))));
+        assert!(late.occurrences(monday.succ().succ(), monday.succ().succ(), &[]).is_empty());
+    }
+
+    #[test]
+    fn event_line_errors() {
+        assert_eq!(Event::parse("Red 2026 1 1 9 1 N 0 \"Café\" \"x none", 1).err(), Some((29, "unterminated quote".to_string())));
+        assert_eq!(Event::parse("Red 2026 1 1 9 1 N 0 \"\" none", 1).err(), Some((22, "missing event name".to_string())));
+    }
+
+    #[test]
+    fn holidays_skip_only_marked_events() {
+        let holidays = vec!(Holiday { date: NaiveDate::from_ymd(4, 10, 20), name: "Day off".to_string(), yearly: true });
+        let tuesday = NaiveDate::from_ymd(2026, 10, 20);
+        assert!(event("Blue 1 1 1 9 1 D 0 Standup none holidays=skip").occurrences(tuesday, tuesday, &holidays).is_empty());
+        assert_eq!(event("Blue 1 1 1 9 1 D 0 Standup none").occurrences(tuesday, tuesday, &holidays).len(), 1);
+    }
+
+    #[test]
+    fn free_weekday_slots() {
+        let query = SlotQuery::parse("2h, weekdays 9-18").unwrap();
+        assert_eq!((query.hours, query.days, query.from_hour, query.to_hour), (2, 0b00111110, 9, 18));
+        let sched_list = vec!(event("Blue 1 1 1 9 3 D 0 Meetings none"), event("Red 1 1 1 13 1 D 0 Lunch none"));
+        // from a Friday morning, over the weekend
+        let slots = free_slots(&sched_list, &query, NaiveDate::from_ymd(2026, 10, 23).and_hms(8, 30, 0), 4, &[]);
+        assert_eq!(slots, vec!(
+            (at(2026, 10, 23, 14), at(2026, 10, 23, 16)), (at(2026, 10, 23, 16), at(2026, 10, 23, 18)),
+            (at(2026, 10, 26, 14), at(2026, 10, 26, 16)), (at(2026, 10, 26, 16), at(2026, 10, 26, 18))));
+        assert!(SlotQuery::parse("weekdays").is_err());
+        assert!(SlotQuery::parse("2h 18-9").is_err());
+    }
+
+    #[test]
+    fn day_patterns_on_month_edges() {
+        let date = |m: u32, d: u32| NaiveDate::from_ymd(2026, m, d);
+        let lastbday = DayPattern::parse("lastbday").unwrap();
+        // October 2026 ends on a Saturday, May 2026 on a Sunday
+        assert!(lastbday.matches(date(10, 30)) && !lastbday.matches(date(10, 31)));
+        assert!(lastbday.matches(date(5, 29)) && !lastbday.matches(date(5, 31)));
+        assert!(lastbday.matches(date(6, 30)));
+        let fifth = DayPattern::parse("5Fri").unwrap();
+        assert!(fifth.matches(date(10, 30)));
+        assert!((1..=30).all(|d| !fifth.matches(date(11, d))));
+        let last = DayPattern::parse("lastFri").unwrap();
+        assert!(last.matches(date(10, 30)) && last.matches(date(11, 27)) && !last.matches(date(10, 23)));
+        assert_eq!(DayPattern::parse("1st"), Some(DayPattern::Day(1)));
+        assert_eq!(DayPattern::parse("2ndTue"), Some(DayPattern::Weekday(2, chrono::Weekday::Tue)));
+        assert_eq!(DayPattern::parse("4Thu"), Some(DayPattern::Weekday(4, chrono::Weekday::Thu)));
+        let invoice = event("Green 1 1 1 9 1 M 0 Invoice none on=1st,15th,lastbday");
+        let days: Vec<u32> = invoice.occurrences(date(10, 1), date(10, 31), &[]).iter().map(|(begin, _)| begin.day()).collect();
+        assert_eq!(days, vec!(1, 15, 30));
+    }
+
+    #[test]
+    fn task_logs_go_to_its_own_block() {
+        let sched_list = vec!(event("Blue 1 1 1 11 3 D 0 Code none"), event("Cyan 2026 10 20 12 1 N 0 \"Write report\" none task=5b0f"));
+        let noon = at(2026, 10, 20, 12);
+        let block = |task: &str| block_in(&sched_list, noon, noon + chrono::Duration::minutes(1), task, &[]).map(|(e, begin)| (e.name, begin));
+        assert_eq!(block("5b0f"), Some(("Write report".to_string(), noon)));
+        assert_eq!(block(""), Some(("Code".to_string(), at(2026, 10, 20, 11))));
+    }
+
+    #[test]
+    fn quoted_names_read_back_from_the_log() {
+        let mut say = event("Blue 2026 10 20 12 1 N 0 Say none");
+        say.name = "Say \"hi\"".to_string();
+        let entry = LogEntry::parse(&LogEntry { begin: at(2026, 10, 20, 12), mark: "done".to_string(), event: say.name.clone(), task: "".to_string() }.to_line()).unwrap();
+        assert_eq!(entry.event, "Say 'hi'");
+        let day = NaiveDate::from_ymd(2026, 10, 20);
+        assert_eq!(planned_actual(&[say], &[entry], day, day, &[]), vec!(("Say \"hi\"".to_string(), 1.0, 1.0)));
+    }
+
+    #[test]
+    fn reminders_fire_once_in_their_window() {
+        let mut standup = event("Blue 2026 10 21 9 1 N 0 Standup none");
+        standup.reminders = vec!(10, 60, 1440);
+        let minute = chrono::Duration::minutes(1);
+        let due = |after: NaiveDateTime, until: NaiveDateTime| due_reminders(&[standup.clone()], after, until, &[]).into_iter().map(|(_, begin)| begin).collect::<Vec<NaiveDateTime>>();
+        // a day ahead fires on the day before
+        assert_eq!(due(at(2026, 10, 20, 9) - minute, at(2026, 10, 20, 9)), vec!(at(2026, 10, 21, 9)));
+        assert_eq!(due(at(2026, 10, 21, 8) - minute, at(2026, 10, 21, 8)), vec!(at(2026, 10, 21, 9)));
+        // after is exclusive and until inclusive
+        assert_eq!(due(at(2026, 10, 21, 8), at(2026, 10, 21, 9) - minute * 10), vec!(at(2026, 10, 21, 9)));
+        assert!(due(at(2026, 10, 21, 9) - minute * 10, at(2026, 10, 21, 9)).is_empty());
+        assert!(due(at(2026, 10, 20, 9), at(2026, 10, 21, 8) - minute).is_empty());
+    }
+
+    #[test]
+    fn notify_runs_the_command_with_name_and_time() {
+        let dir = std::env::temp_dir();
+        let (script, out) = (dir.join(format!("taskcalrs-notify-{}.sh", std::process::id())), dir.join(format!("taskcalrs-notify-{}.out", std::process::id())));
+        std::fs::write(&script, format!("echo \"$1|$2\" > {}\n", out.display())).unwrap();
+        let command = vec!("sh".to_string(), script.to_str().unwrap().to_string());
+        notify(&command, &event("Blue 1 1 1 9 1 D 0 \"Team Lunch\" none"), at(2026, 10, 21, 12));
+        let mut written = "".to_string();
+        for _ in 0..100 {
+            written = std::fs::read_to_string(&out).unwrap_or("".to_string());
+            if written.ends_with('\n') { break }
+            thread::sleep(Duration::from_millis(20));
+        }
+        std::fs::remove_file(&script).unwrap();
+        std::fs::remove_file(&out).unwrap();
+        assert_eq!(written, "Team Lunch|12:00\n");
+    }
+
+    #[test]
+    fn weekday_masks_only_on_weekly_events() {
+        let entries = vec!(Ok(event("Red 1 1 1 9 1 W 00111110 Work none")), Ok(event("Red 1 1 1 12 1 D 01010010 Lift none")), Ok(event("Red 1 1 1 18 1 D 0 Dinner none")));
+        let lints: Vec<String> = lint_schedule(&entries, NaiveDate::from_ymd(2026, 10, 19), NaiveDate::from_ymd(2026, 10, 19), &[]).into_iter().map(|l| l.message).collect();
+        assert_eq!(lints, vec!("Lift has a weekday mask but repeats daily, it only applies to weekly events"));
+    }
+}